@@ -0,0 +1,138 @@
+use fixedbitset::FixedBitSet;
+
+use crate::{
+    graph::TGraph,
+    lattice::{Direction, Lattice},
+};
+
+/// Search for a cover of the complete graph with `num_nodes` nodes, that uses
+/// at most `max_lattices` lattices. Returns `None` if the search finds no such
+/// cover.
+///
+/// This explores the same moves as the greedy solver, but instead of
+/// committing to the first move that works, it backtracks out of dead ends.
+/// The search space is restricted: each lattice starts from the first
+/// remaining edge, is grown one node at a time into the empty slots, and stays
+/// connected, and no edge is covered twice. Covers that need lattices with more
+/// than one component, or lattices that overlap, are never tried. So `None` is
+/// not a proof that no cover with `max_lattices` lattices exists, and can't be
+/// used as a lower bound. The search is exponential, so this is only practical
+/// for small graphs.
+pub fn solve_backtracking<G>(num_nodes: usize, max_lattices: usize) -> Option<Vec<Lattice>>
+where
+    G: TGraph,
+{
+    let graph = G::new_complete(num_nodes);
+    let mut out = Vec::new();
+    let (a, b) = match first_edge(&graph) {
+        Some(edge) => edge,
+        None => return Some(out),
+    };
+    if max_lattices == 0 {
+        return None;
+    }
+    let (lattice, graph) = start_lattice(&graph, a, b);
+    if search(&graph, &lattice, max_lattices, &mut out) {
+        Some(out)
+    } else {
+        None
+    }
+}
+
+fn first_edge<G: TGraph>(graph: &G) -> Option<(u32, u32)> {
+    (0..(graph.num_nodes() as u32)).find_map(|i| graph.edges(i).next().map(|j| (i, j)))
+}
+
+fn start_lattice<G: TGraph>(graph: &G, a: u32, b: u32) -> (Lattice, G) {
+    let mut lattice = Lattice::new(graph.num_nodes());
+    lattice.insert(a, Direction::RIGHT, b);
+    let mut graph = graph.clone();
+    graph.remove_edge(a, b);
+    (lattice, graph)
+}
+
+/// Try to cover the remaining `graph`, starting by growing `lattice`, using at
+/// most `budget` lattices including `lattice`. On success, the lattices are
+/// appended to `out`.
+fn search<G: TGraph>(graph: &G, lattice: &Lattice, budget: usize, out: &mut Vec<Lattice>) -> bool {
+    if graph.is_empty() {
+        out.push(lattice.clone());
+        return true;
+    }
+    let mut required = Vec::new();
+    let mut candidates = FixedBitSet::new();
+    for (id, dir, nbs) in lattice.empty_slots_iter() {
+        required.clear();
+        required.extend(nbs.iter().filter_map(|n| n.get()));
+        if required.is_empty() {
+            continue;
+        }
+        graph.find_candidates(&required, &mut candidates);
+        for c in candidates.ones().map(|c| c as u32) {
+            if lattice.contains(c) {
+                continue;
+            }
+            let mut next = lattice.clone();
+            next.insert(id, dir, c);
+            // Inserting can link the new node to more than just the required
+            // nodes. All of those edges must still be uncovered.
+            if next.neighbors(c).any(|nb| !graph.has_edge(c, nb)) {
+                continue;
+            }
+            let mut remaining = graph.clone();
+            for nb in next.neighbors(c) {
+                remaining.remove_edge(c, nb);
+            }
+            if search(&remaining, &next, budget, out) {
+                return true;
+            }
+        }
+    }
+    // Close this lattice and start a new one. Every edge has to be covered by
+    // some lattice, so it is enough to only try starting from the first
+    // remaining edge.
+    if budget > 1
+        && let Some((a, b)) = first_edge(graph)
+    {
+        let (next, remaining) = start_lattice(graph, a, b);
+        out.push(lattice.clone());
+        if search(&remaining, &next, budget - 1, out) {
+            return true;
+        }
+        out.pop();
+    }
+    false
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{graph::Graph, greedy::solve_greedy, verify::assert_covers};
+
+    #[test]
+    fn test_backtracking_no_worse_than_greedy() {
+        for n in [3, 4, 5] {
+            let greedy = solve_greedy::<Graph>(n);
+            let solution = solve_backtracking::<Graph>(n, greedy.len())
+                .expect("Greedy found a cover, so backtracking should too");
+            assert!(solution.len() <= greedy.len());
+            assert_covers(n, &solution);
+        }
+    }
+
+    #[test]
+    fn test_backtracking_infeasible() {
+        // K4 cannot be embedded in a single triangular lattice.
+        assert!(solve_backtracking::<Graph>(4, 1).is_none());
+        assert!(solve_backtracking::<Graph>(5, 1).is_none());
+        assert!(solve_backtracking::<Graph>(4, 0).is_none());
+    }
+
+    #[test]
+    fn test_backtracking_trivial() {
+        assert_eq!(solve_backtracking::<Graph>(1, 0).map(|s| s.len()), Some(0));
+        let solution = solve_backtracking::<Graph>(3, 1).unwrap();
+        assert_eq!(solution.len(), 1);
+        assert_covers(3, &solution);
+    }
+}
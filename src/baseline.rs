@@ -0,0 +1,81 @@
+use crate::{
+    graph::TGraph,
+    lattice::{Direction, Lattice},
+};
+
+/// Cover the complete graph with `num_nodes` nodes using a 1-factorization,
+/// i.e. split the edges into rounds of a round-robin tournament, where every
+/// node plays at most one match per round. Each round is a matching, and
+/// becomes one lattice of disjoint edges. This needs `num_nodes - 1` lattices
+/// for an even number of nodes and `num_nodes` for an odd number, and is meant
+/// as a baseline to compare the other solvers against.
+pub fn cover_by_bipartite_layers<G>(num_nodes: usize) -> Vec<Lattice>
+where
+    G: TGraph,
+{
+    if num_nodes < 2 {
+        return Vec::new();
+    }
+    // With an odd number of nodes, add a dummy node. Whoever plays the dummy
+    // sits that round out.
+    let players = (num_nodes + num_nodes % 2) as u32;
+    let dummy = players - 1;
+    let mut graph = G::new_complete(num_nodes);
+    let mut out = Vec::with_capacity(players as usize - 1);
+    for round in 0..(players - 1) {
+        // Keep the last player fixed and rotate everyone else.
+        let pairs = std::iter::once((round, players - 1)).chain((1..(players / 2)).map(|k| {
+            (
+                (round + k) % (players - 1),
+                (round + players - 1 - k) % (players - 1),
+            )
+        }));
+        let mut lattice = Lattice::new(num_nodes);
+        for (a, b) in pairs {
+            if num_nodes % 2 == 1 && (a == dummy || b == dummy) {
+                continue;
+            }
+            debug_assert!(graph.has_edge(a, b), "Edge ({a}, {b}) is already covered");
+            graph.remove_edge(a, b);
+            lattice.insert(a, Direction::RIGHT, b);
+        }
+        out.push(lattice);
+    }
+    debug_assert!(graph.is_empty(), "The rounds must cover every edge");
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{graph::Graph, greedy::solve_greedy, verify::verify_cover};
+
+    #[test]
+    fn test_bipartite_layers_cover() {
+        for n in 0..16 {
+            let solution = cover_by_bipartite_layers::<Graph>(n);
+            assert_eq!(verify_cover(n, &solution), Ok(()));
+            for lattice in &solution {
+                lattice.validate();
+                // Every lattice is a matching.
+                assert!(lattice.iter_nodes().all(|id| lattice.degree(id) == 1));
+            }
+            let expected = match n {
+                0 | 1 => 0,
+                n => n - 1 + n % 2,
+            };
+            assert_eq!(solution.len(), expected);
+        }
+    }
+
+    #[test]
+    fn test_bipartite_layers_vs_greedy() {
+        let n = 12;
+        let baseline = cover_by_bipartite_layers::<Graph>(n);
+        let greedy = solve_greedy::<Graph>(n);
+        assert_eq!(verify_cover(n, &baseline), Ok(()));
+        assert_eq!(verify_cover(n, &greedy), Ok(()));
+        assert_eq!(baseline.len(), 11);
+        assert!(greedy.len() < baseline.len());
+    }
+}
@@ -0,0 +1,60 @@
+/// Upper bound on the number of edges in a single lattice with `num_nodes`
+/// nodes. A triangular lattice is a planar graph, so it can have at most
+/// `3v - 6` edges. The bound is not tight, e.g. 4 nodes can only form a
+/// rhombus with 5 edges.
+pub fn max_lattice_edges(num_nodes: usize) -> usize {
+    match num_nodes {
+        0 | 1 => 0,
+        2 => 1,
+        v => 3 * v - 6,
+    }
+}
+
+/// A lower bound on the number of lattices needed to cover the complete graph
+/// with `num_nodes` nodes. Every lattice covers at most
+/// `max_lattice_edges` edges, so we need at least
+/// `ceil(num_edges / max_lattice_edges)` of them.
+pub fn lower_bound(num_nodes: usize) -> usize {
+    let num_edges = num_nodes * num_nodes.saturating_sub(1) / 2;
+    match max_lattice_edges(num_nodes) {
+        0 => 0,
+        max => num_edges.div_ceil(max),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{graph::Graph, greedy::solve_greedy};
+
+    #[test]
+    fn test_lower_bound_small() {
+        assert_eq!(lower_bound(0), 0);
+        assert_eq!(lower_bound(1), 0);
+        assert_eq!(lower_bound(2), 1);
+        assert_eq!(lower_bound(3), 1);
+        assert_eq!(lower_bound(4), 1);
+        // K9 has 36 edges, and a lattice on 9 nodes has at most 21.
+        assert_eq!(lower_bound(9), 2);
+    }
+
+    #[test]
+    fn test_max_lattice_edges() {
+        assert_eq!(max_lattice_edges(2), 1);
+        assert_eq!(max_lattice_edges(3), 3);
+        assert_eq!(max_lattice_edges(4), 6);
+        assert_eq!(max_lattice_edges(10), 24);
+        for n in 3..16 {
+            for lattice in solve_greedy::<Graph>(n) {
+                assert!(lattice.edge_count() <= max_lattice_edges(n));
+            }
+        }
+    }
+
+    #[test]
+    fn test_lower_bound_below_greedy() {
+        for n in 3..16 {
+            assert!(lower_bound(n) <= solve_greedy::<Graph>(n).len());
+        }
+    }
+}
@@ -0,0 +1,414 @@
+use std::fmt::Write;
+
+use crate::lattice::{Direction, Lattice};
+
+/// Distance between adjacent nodes in an SVG drawing.
+const SVG_SCALE: f64 = 40.0;
+/// Space around the lattice in an SVG drawing.
+const SVG_MARGIN: f64 = 20.0;
+
+/// Serialize `solutions` as a JSON array of lattices, where each lattice is an
+/// array of its edges, and each edge is a `[a, b]` pair with `a < b`.
+pub fn to_json(solutions: &[Lattice]) -> String {
+    let mut out = String::from("[");
+    for (i, lattice) in solutions.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push('[');
+        for (j, (a, b)) in lattice.edges().enumerate() {
+            if j > 0 {
+                out.push(',');
+            }
+            write!(out, "[{a},{b}]").expect("Writing to a String cannot fail");
+        }
+        out.push(']');
+    }
+    out.push(']');
+    out
+}
+
+/// Write `solutions` as CSV, with one row per lattice. The first column is the
+/// index of the lattice, and the remaining columns are its edges, written as
+/// `a-b` with `a < b`. The first row is a header.
+pub fn solution_to_csv(solutions: &[Lattice]) -> String {
+    let mut out = String::from("lattice,edges\n");
+    for (i, lattice) in solutions.iter().enumerate() {
+        write!(out, "{i}").expect("Writing to a String cannot fail");
+        for (a, b) in lattice.edges() {
+            write!(out, ",{a}-{b}").expect("Writing to a String cannot fail");
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Returned by [`to_rounds`] when a node plays more than once in a round.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct NotAMatching {
+    /// Index of the offending round.
+    pub round: usize,
+    /// The first node that plays more than once in that round.
+    pub node: u32,
+}
+
+impl std::fmt::Display for NotAMatching {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Node {} plays more than once in round {}",
+            self.node, self.round
+        )
+    }
+}
+
+impl std::error::Error for NotAMatching {}
+
+/// Interpret each lattice in `solutions` as a round of a tournament, where
+/// every edge is a pairing played in that round. Returns the pairings of each
+/// round, as `(a, b)` with `a < b`. If `disjoint` is set, every round must be
+/// a matching, i.e. no node can play twice in the same round, as in
+/// `baseline::cover_by_bipartite_layers`. The lattices from the greedy solvers
+/// contain triangles, so they fail this check.
+pub fn to_rounds(
+    solutions: &[Lattice],
+    disjoint: bool,
+) -> Result<Vec<Vec<(u32, u32)>>, NotAMatching> {
+    solutions
+        .iter()
+        .enumerate()
+        .map(|(round, lattice)| {
+            if disjoint && let Some(node) = lattice.iter_nodes().find(|&n| lattice.degree(n) > 1) {
+                return Err(NotAMatching { round, node });
+            }
+            Ok(lattice.edges().collect())
+        })
+        .collect()
+}
+
+/// Summarize `solutions` as a text table, with one row per lattice after the
+/// header row. The columns are the index of the lattice, and its number of
+/// nodes, edges, triangular faces and connected components.
+pub fn print_summary(solutions: &[Lattice]) -> String {
+    let mut out = String::from("lattice  nodes  edges  faces  components\n");
+    for (i, lattice) in solutions.iter().enumerate() {
+        writeln!(
+            out,
+            "{i:>7}  {:>5}  {:>5}  {:>5}  {:>10}",
+            lattice.iter_nodes().count(),
+            lattice.edge_count(),
+            lattice.triangle_count(),
+            lattice.components().len()
+        )
+        .expect("Writing to a String cannot fail");
+    }
+    out
+}
+
+/// Lay out `lattice` on the plane, with adjacent nodes a unit distance apart,
+/// and the y axis pointing down. Components are placed side by side. Returns
+/// the position of each node, indexed by node id, along with the width and
+/// height of the layout.
+fn layout(lattice: &Lattice) -> (Vec<Option<(f64, f64)>>, f64, f64) {
+    let mut positions: Vec<Option<(f64, f64)>> = vec![None; lattice.len()];
+    let mut stack = Vec::new();
+    let mut nodes = Vec::new();
+    let mut cursor = 0.0;
+    for start in 0..(lattice.len() as u32) {
+        if positions[start as usize].is_some() || !lattice.contains(start) {
+            continue;
+        }
+        positions[start as usize] = Some((0.0, 0.0));
+        stack.push(start);
+        nodes.clear();
+        while let Some(id) = stack.pop() {
+            nodes.push(id);
+            let (x, y) = positions[id as usize].expect("Nodes are placed before visiting");
+            for dir in Direction::all() {
+                if let Some(nb) = lattice.neighbor(id, dir)
+                    && positions[nb as usize].is_none()
+                {
+                    let (dx, dy) = dir.to_unit_vector();
+                    positions[nb as usize] = Some((x + dx, y - dy));
+                    stack.push(nb);
+                }
+            }
+        }
+        let (xmin, xmax) = nodes
+            .iter()
+            .filter_map(|&id| positions[id as usize])
+            .fold((f64::MAX, f64::MIN), |(lo, hi), (x, _)| {
+                (lo.min(x), hi.max(x))
+            });
+        for &id in &nodes {
+            if let Some((x, _)) = &mut positions[id as usize] {
+                *x += cursor - xmin;
+            }
+        }
+        cursor += xmax - xmin + 1.0;
+    }
+    let (ymin, ymax) = positions
+        .iter()
+        .flatten()
+        .fold((f64::MAX, f64::MIN), |(lo, hi), &(_, y)| {
+            (lo.min(y), hi.max(y))
+        });
+    if ymin > ymax {
+        return (positions, 0.0, 0.0);
+    }
+    for (_, y) in positions.iter_mut().flatten() {
+        *y -= ymin;
+    }
+    (positions, (cursor - 1.0).max(0.0), ymax - ymin)
+}
+
+/// Draw the edges and nodes of a lattice laid out by `layout`.
+fn write_svg_body(
+    out: &mut String,
+    lattice: &Lattice,
+    positions: &[Option<(f64, f64)>],
+) -> std::fmt::Result {
+    let at = |id: u32| {
+        positions[id as usize]
+            .map(|(x, y)| (x * SVG_SCALE + SVG_MARGIN, y * SVG_SCALE + SVG_MARGIN))
+    };
+    for (a, b) in lattice.edges() {
+        if let (Some((x1, y1)), Some((x2, y2))) = (at(a), at(b)) {
+            writeln!(
+                out,
+                r#"<line x1="{x1:.2}" y1="{y1:.2}" x2="{x2:.2}" y2="{y2:.2}" stroke="black"/>"#
+            )?;
+        }
+    }
+    for id in 0..(lattice.len() as u32) {
+        if let Some((x, y)) = at(id) {
+            writeln!(
+                out,
+                r#"<circle cx="{x:.2}" cy="{y:.2}" r="10" fill="white" stroke="black"/>"#
+            )?;
+            writeln!(
+                out,
+                r#"<text x="{x:.2}" y="{y:.2}" text-anchor="middle" dominant-baseline="central" font-size="10">{id}</text>"#
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn write_svg_header(out: &mut String, width: f64, height: f64) -> std::fmt::Result {
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width:.2}" height="{height:.2}" viewBox="0 0 {width:.2} {height:.2}">"#
+    )
+}
+
+/// Draw `lattice` as an SVG image.
+pub fn to_svg(lattice: &Lattice) -> String {
+    let mut out = String::new();
+    write_lattice_svg(&mut out, lattice).expect("Writing to a String cannot fail");
+    out
+}
+
+fn write_lattice_svg(out: &mut String, lattice: &Lattice) -> std::fmt::Result {
+    let (positions, width, height) = layout(lattice);
+    write_svg_header(
+        out,
+        width * SVG_SCALE + 2.0 * SVG_MARGIN,
+        height * SVG_SCALE + 2.0 * SVG_MARGIN,
+    )?;
+    out.push_str("<g>\n");
+    write_svg_body(out, lattice, &positions)?;
+    out.push_str("</g>\n</svg>\n");
+    Ok(())
+}
+
+/// Draw all the lattices in `solutions` on a single SVG image, arranged in a
+/// grid. Each lattice is drawn like `to_svg`, in its own group, and labeled with
+/// its index.
+pub fn solution_to_svg(solutions: &[Lattice]) -> String {
+    let mut out = String::new();
+    write_solution_svg(&mut out, solutions).expect("Writing to a String cannot fail");
+    out
+}
+
+fn write_solution_svg(out: &mut String, solutions: &[Lattice]) -> std::fmt::Result {
+    let layouts: Vec<_> = solutions.iter().map(layout).collect();
+    let cell_width =
+        layouts.iter().fold(0.0f64, |w, (_, lw, _)| w.max(*lw)) * SVG_SCALE + 2.0 * SVG_MARGIN;
+    // Leave room for the label above each lattice.
+    let cell_height =
+        layouts.iter().fold(0.0f64, |h, (_, _, lh)| h.max(*lh)) * SVG_SCALE + 3.0 * SVG_MARGIN;
+    let columns = (solutions.len() as f64).sqrt().ceil().max(1.0) as usize;
+    let rows = solutions.len().div_ceil(columns);
+    write_svg_header(out, columns as f64 * cell_width, rows as f64 * cell_height)?;
+    for (i, (lattice, (positions, _, _))) in solutions.iter().zip(&layouts).enumerate() {
+        let x = (i % columns) as f64 * cell_width;
+        let y = (i / columns) as f64 * cell_height;
+        writeln!(out, r#"<g transform="translate({x:.2} {y:.2})">"#)?;
+        writeln!(
+            out,
+            r#"<text x="{SVG_MARGIN:.2}" y="{SVG_MARGIN:.2}" font-size="14">#{i}</text>"#
+        )?;
+        // Shift the lattice below its label.
+        writeln!(out, r#"<svg y="{SVG_MARGIN:.2}">"#)?;
+        write_svg_body(out, lattice, positions)?;
+        out.push_str("</svg>\n</g>\n");
+    }
+    out.push_str("</svg>\n");
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lattice::Direction;
+
+    #[test]
+    fn test_to_json() {
+        assert_eq!(to_json(&[]), "[]");
+        let mut triangle = Lattice::new(4);
+        triangle.insert(0, Direction::RIGHT, 1);
+        triangle.insert(0, Direction::TOP_RIGHT, 2);
+        let mut edge = Lattice::new(4);
+        edge.insert(1, Direction::RIGHT, 3);
+        assert_eq!(
+            to_json(&[triangle, edge, Lattice::new(4)]),
+            "[[[0,1],[0,2],[1,2]],[[1,3]],[]]"
+        );
+    }
+
+    #[test]
+    fn test_to_svg_triangle() {
+        let mut triangle = Lattice::new(3);
+        triangle.insert(0, Direction::RIGHT, 1);
+        triangle.insert(0, Direction::TOP_RIGHT, 2);
+        let svg = to_svg(&triangle);
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches("<line").count(), 3);
+        assert_eq!(svg.matches("<circle").count(), 3);
+        assert_eq!(svg.matches("<g>").count(), 1);
+    }
+
+    #[test]
+    fn test_solution_to_svg_groups() {
+        use crate::{graph::Graph, greedy::solve_greedy};
+        let solutions = solve_greedy::<Graph>(8);
+        let svg = solution_to_svg(&solutions);
+        assert_eq!(svg.matches("<g").count(), solutions.len());
+        let edges: usize = solutions.iter().map(|l| l.edge_count()).sum();
+        assert_eq!(svg.matches("<line").count(), edges);
+        assert_eq!(solution_to_svg(&[]).matches("<g").count(), 0);
+    }
+
+    #[test]
+    fn test_to_rounds() {
+        use crate::{
+            baseline::cover_by_bipartite_layers, graph::Graph, greedy::solve_greedy_disjoint,
+        };
+        for n in 2..12 {
+            let solutions = cover_by_bipartite_layers::<Graph>(n);
+            let rounds = to_rounds(&solutions, true).expect("Bipartite layers are matchings");
+            assert_eq!(rounds.len(), solutions.len());
+            for round in &rounds {
+                // Every node plays at most once per round.
+                let mut nodes: Vec<u32> = round.iter().flat_map(|&(a, b)| [a, b]).collect();
+                nodes.sort();
+                let count = nodes.len();
+                nodes.dedup();
+                assert_eq!(nodes.len(), count);
+            }
+            // Every pair plays exactly once.
+            let mut pairs: Vec<_> = rounds.concat();
+            pairs.sort();
+            assert_eq!(pairs.len(), n * (n - 1) / 2);
+            pairs.dedup();
+            assert_eq!(pairs.len(), n * (n - 1) / 2);
+            assert!(pairs.iter().all(|&(a, b)| a < b));
+        }
+        // The disjoint greedy cover schedules every pair once, but its rounds
+        // contain triangles, so they aren't matchings.
+        let greedy = solve_greedy_disjoint::<Graph>(9);
+        let rounds = to_rounds(&greedy, false).expect("Only disjoint mode checks matchings");
+        assert_eq!(rounds.concat().len(), 36);
+        let err = to_rounds(&greedy, true).unwrap_err();
+        assert!(greedy[err.round].degree(err.node) > 1);
+        assert!(
+            greedy[..err.round]
+                .iter()
+                .all(|l| l.iter_nodes().all(|n| l.degree(n) <= 1))
+        );
+        assert_eq!(to_rounds(&[], true), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_to_rounds_rejects_shared_node() {
+        let mut matching = Lattice::new(4);
+        matching.insert(0, Direction::RIGHT, 1);
+        let mut path = Lattice::new(4);
+        path.insert(2, Direction::RIGHT, 1);
+        path.insert(1, Direction::RIGHT, 3);
+        assert_eq!(
+            to_rounds(&[matching.clone(), path.clone()], true),
+            Err(NotAMatching { round: 1, node: 1 })
+        );
+        assert_eq!(
+            to_rounds(&[matching, path], false),
+            Ok(vec![vec![(0, 1)], vec![(1, 3), (1, 2)]])
+        );
+    }
+
+    #[test]
+    fn test_print_summary() {
+        use crate::{graph::Graph, greedy::solve_greedy};
+        let n = 9;
+        let solutions = solve_greedy::<Graph>(n);
+        let summary = print_summary(&solutions);
+        let mut lines = summary.lines();
+        assert_eq!(
+            lines
+                .next()
+                .map(|h| h.split_whitespace().collect::<Vec<_>>()),
+            Some(vec!["lattice", "nodes", "edges", "faces", "components"])
+        );
+        let rows: Vec<Vec<usize>> = lines
+            .map(|line| {
+                line.split_whitespace()
+                    .map(|c| c.parse().unwrap())
+                    .collect()
+            })
+            .collect();
+        assert_eq!(rows.len(), solutions.len());
+        for (i, row) in rows.iter().enumerate() {
+            assert_eq!(row[0], i);
+        }
+        let edges: usize = solutions.iter().map(|l| l.edge_count()).sum();
+        assert_eq!(rows.iter().map(|row| row[2]).sum::<usize>(), edges);
+        assert_eq!(print_summary(&[]).lines().count(), 1);
+    }
+
+    #[test]
+    fn test_solution_to_csv_round_trip() {
+        use crate::{
+            graph::{Graph, TGraph},
+            greedy::solve_greedy,
+            verify::verify_cover,
+        };
+        let n = 9;
+        let solutions = solve_greedy::<Graph>(n);
+        assert_eq!(verify_cover(n, &solutions), Ok(()));
+        let csv = solution_to_csv(&solutions);
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("lattice,edges"));
+        let mut graph = Graph::new_complete(n);
+        for (i, line) in lines.enumerate() {
+            let mut columns = line.split(',');
+            assert_eq!(columns.next(), Some(i.to_string().as_str()));
+            for edge in columns {
+                let (a, b) = edge.split_once('-').unwrap();
+                graph.remove_edge(a.parse().unwrap(), b.parse().unwrap());
+            }
+        }
+        assert!(graph.is_empty());
+        assert_eq!(solution_to_csv(&[]), "lattice,edges\n");
+    }
+}
@@ -1,22 +1,125 @@
 use fixedbitset::FixedBitSet;
-use std::fmt::Display;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use std::fmt::{Display, Write};
+
+use crate::lattice::Lattice;
 
 pub trait TGraph: Clone + Display {
     fn new_complete(n: usize) -> Self;
     fn has_edge(&self, i: u32, j: u32) -> bool;
     fn remove_edge(&mut self, i: u32, j: u32);
+    /// Add the edge `(i, j)` back. Self loops are ignored.
+    fn add_edge(&mut self, i: u32, j: u32);
     fn num_edges(&self) -> usize;
     fn is_empty(&self) -> bool;
     fn valence(&self, node: u32) -> usize;
     fn find_candidates(&self, required: &[u32], candidates: &mut FixedBitSet);
+    /// Update `candidates` found by `find_candidates` after `node` is added to
+    /// the required nodes, i.e. keep only the candidates connected to `node`.
+    fn find_candidates_add(&self, node: u32, candidates: &mut FixedBitSet);
     fn num_nodes(&self) -> usize;
     fn edges(&self, id: u32) -> impl Iterator<Item = u32>;
+
+    /// Iterate over all the remaining edges. Each edge is reported once, as
+    /// `(i, j)` with `i < j`, in ascending order.
+    fn all_edges(&self) -> impl Iterator<Item = (u32, u32)> {
+        (0..(self.num_nodes() as u32))
+            .flat_map(move |i| self.edges(i).filter(move |&j| i < j).map(move |j| (i, j)))
+    }
+
+    /// The node with the most remaining edges. Ties are broken in favor of the
+    /// smallest node. Returns `None` if the graph has no edges.
+    fn max_valence_node(&self) -> Option<u32> {
+        if self.is_empty() {
+            return None;
+        }
+        (0..(self.num_nodes() as u32))
+            .rev()
+            .max_by_key(|&node| self.valence(node))
+    }
+
+    /// Copy this graph into `dst`, reusing the buffers of `dst` where the
+    /// implementation can.
+    fn copy_into(&self, dst: &mut Self) {
+        dst.clone_from(self);
+    }
+
+    /// Remove all the given edges. Edges that are already gone are skipped.
+    fn remove_edges<I: IntoIterator<Item = (u32, u32)>>(&mut self, edges: I) {
+        for (a, b) in edges {
+            self.remove_edge(a, b);
+        }
+    }
+
+    /// Remove all the edges, keeping the nodes.
+    fn clear(&mut self) {
+        let edges: Vec<_> = self.all_edges().collect();
+        self.remove_edges(edges);
+    }
+
+    /// Remove all the edges of `lattice` from this graph.
+    fn remove_lattice(&mut self, lattice: &Lattice) {
+        self.remove_edges(lattice.edges());
+    }
+}
+
+/// Reasons why [`Graph::from_adjacency_matrix`] rejected a matrix.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ShapeError {
+    /// This row doesn't have as many entries as there are rows.
+    NotSquare { row: usize, len: usize },
+    /// Entries `(i, j)` and `(j, i)` differ.
+    NotSymmetric(usize, usize),
+    /// The diagonal entry of this node is set.
+    SelfLoop(usize),
+}
+
+impl Display for ShapeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShapeError::NotSquare { row, len } => {
+                write!(
+                    f,
+                    "Row {row} has {len} entries, so the matrix is not square"
+                )
+            }
+            ShapeError::NotSymmetric(i, j) => {
+                write!(f, "Entries ({i}, {j}) and ({j}, {i}) are not the same")
+            }
+            ShapeError::SelfLoop(i) => write!(f, "Diagonal entry ({i}, {i}) is set"),
+        }
+    }
 }
 
-#[derive(Clone)]
+impl std::error::Error for ShapeError {}
+
+#[derive(Debug, PartialEq, Eq)]
 pub struct Graph {
     n_nodes: usize,
     conn: Vec<FixedBitSet>,
+    /// Number of edges in the graph, kept up to date by `add_edge` and
+    /// `remove_edge`, so that `num_edges` and `is_empty` don't have to scan
+    /// the bitsets.
+    remaining_edges: usize,
+    /// Number of edges of each node, kept up to date like `remaining_edges`.
+    valences: Vec<usize>,
+}
+
+impl Clone for Graph {
+    fn clone(&self) -> Self {
+        Self {
+            n_nodes: self.n_nodes,
+            conn: self.conn.clone(),
+            remaining_edges: self.remaining_edges,
+            valences: self.valences.clone(),
+        }
+    }
+
+    /// Reuses the bitsets of `self`, so copying between graphs of the same size
+    /// doesn't allocate.
+    fn clone_from(&mut self, source: &Self) {
+        source.copy_into(self);
+    }
 }
 
 impl TGraph for Graph {
@@ -34,6 +137,8 @@ impl TGraph for Graph {
         Self {
             n_nodes: n,
             conn: neighbors,
+            remaining_edges: n * n.saturating_sub(1) / 2,
+            valences: vec![n.saturating_sub(1); n],
         }
     }
 
@@ -42,40 +147,93 @@ impl TGraph for Graph {
     }
 
     fn remove_edge(&mut self, i: u32, j: u32) {
-        self.conn[i as usize].remove(j as usize);
-        self.conn[j as usize].remove(i as usize);
+        // Removing an edge that is already gone must not touch the count.
+        if self.has_edge(i, j) {
+            self.conn[i as usize].remove(j as usize);
+            self.conn[j as usize].remove(i as usize);
+            self.remaining_edges -= 1;
+            self.valences[i as usize] -= 1;
+            self.valences[j as usize] -= 1;
+        }
+    }
+
+    fn add_edge(&mut self, i: u32, j: u32) {
+        if i != j && !self.has_edge(i, j) {
+            self.conn[i as usize].insert(j as usize);
+            self.conn[j as usize].insert(i as usize);
+            self.remaining_edges += 1;
+            self.valences[i as usize] += 1;
+            self.valences[j as usize] += 1;
+        }
     }
 
     fn edges(&self, id: u32) -> impl Iterator<Item = u32> {
-        self.conn[id as usize].ones().map(|n| n as u32)
+        self.neighbors(id)
     }
 
     fn num_edges(&self) -> usize {
-        self.conn.iter().map(|n| n.count_ones(..)).sum::<usize>() / 2
+        self.remaining_edges
     }
 
     fn is_empty(&self) -> bool {
-        self.conn.iter().all(|n| n.is_clear())
+        self.remaining_edges == 0
     }
 
     fn valence(&self, node: u32) -> usize {
-        self.conn[node as usize].count_ones(..)
+        self.valences[node as usize]
+    }
+
+    fn max_valence_node(&self) -> Option<u32> {
+        if self.is_empty() {
+            return None;
+        }
+        self.valences
+            .iter()
+            .enumerate()
+            .rev()
+            .max_by_key(|&(_, &valence)| valence)
+            .map(|(node, _)| node as u32)
+    }
+
+    fn copy_into(&self, dst: &mut Self) {
+        dst.n_nodes = self.n_nodes;
+        dst.conn.truncate(self.n_nodes);
+        for (row, src) in dst.conn.iter_mut().zip(&self.conn) {
+            row.clone_from(src);
+        }
+        let len = dst.conn.len();
+        dst.conn.extend_from_slice(&self.conn[len..]);
+        dst.remaining_edges = self.remaining_edges;
+        dst.valences.clone_from(&self.valences);
+    }
+
+    fn clear(&mut self) {
+        for nbs in &mut self.conn {
+            nbs.clear();
+        }
+        self.valences.fill(0);
+        self.remaining_edges = 0;
     }
 
     fn find_candidates(&self, required: &[u32], candidates: &mut FixedBitSet) {
-        candidates.clear();
-        candidates.grow_and_insert(self.n_nodes);
         if required.is_empty() {
+            candidates.clear();
+            candidates.grow(self.n_nodes);
+            candidates.insert_range(..self.n_nodes);
             return;
         }
         // Start with neighbors of first required node
         candidates.clone_from(&self.conn[required[0] as usize]);
         // Intersect with neighbors of each subsequent required node
         for &node in &required[1..] {
-            candidates.intersect_with(&self.conn[node as usize]);
+            self.find_candidates_add(node, candidates);
         }
     }
 
+    fn find_candidates_add(&self, node: u32, candidates: &mut FixedBitSet) {
+        candidates.intersect_with(&self.conn[node as usize]);
+    }
+
     fn num_nodes(&self) -> usize {
         self.n_nodes
     }
@@ -83,6 +241,193 @@ impl TGraph for Graph {
 
 impl Display for Graph {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write_matrix(f, false, None)
+    }
+}
+
+impl Graph {
+    /// Create a graph with `n` nodes and no edges.
+    pub fn new_empty(n: usize) -> Self {
+        Self {
+            n_nodes: n,
+            conn: vec![FixedBitSet::with_capacity(n); n],
+            remaining_edges: 0,
+            valences: vec![0; n],
+        }
+    }
+
+    /// Nodes that share a remaining edge with `node`, in ascending order.
+    pub fn neighbors(&self, node: u32) -> impl Iterator<Item = u32> {
+        self.conn[node as usize].ones().map(|n| n as u32)
+    }
+
+    /// Create the complete graph with `n` nodes, without the edges in
+    /// `matching`. Used for round robin schedules where some pairs of nodes
+    /// never meet.
+    pub fn new_complete_minus_matching(n: usize, matching: &[(u32, u32)]) -> Self {
+        let mut out = Self::new_complete(n);
+        out.remove_edges(matching.iter().copied());
+        out
+    }
+
+    /// Create a random graph with `n` nodes, where each possible edge is
+    /// included with probability `edge_prob`. The same `seed` always produces
+    /// the same graph. Panics if `edge_prob` is not in `[0, 1]`.
+    pub fn new_random(n: usize, edge_prob: f64, seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut out = Self::new_empty(n);
+        for i in 0..(n as u32) {
+            for j in (i + 1)..(n as u32) {
+                if rng.random_bool(edge_prob) {
+                    out.add_edge(i, j);
+                }
+            }
+        }
+        out
+    }
+
+    /// Number of nodes with each valence, i.e. index `d` holds the number of
+    /// nodes with `d` remaining edges. The histogram ends at the largest
+    /// valence, and is empty if the graph has no nodes.
+    pub fn valence_histogram(&self) -> Vec<usize> {
+        let mut out = vec![0; self.valences.iter().max().map_or(0, |max| max + 1)];
+        for &valence in &self.valences {
+            out[valence] += 1;
+        }
+        out
+    }
+
+    /// Whether every pair of nodes is still connected by an edge.
+    pub fn is_complete(&self) -> bool {
+        self.num_edges() == self.max_edges()
+    }
+
+    /// Fraction of all possible edges that remain in the graph. A graph with
+    /// fewer than two nodes has no possible edges, and a density of zero.
+    pub fn edge_density(&self) -> f64 {
+        match self.max_edges() {
+            0 => 0.0,
+            max => self.num_edges() as f64 / max as f64,
+        }
+    }
+
+    fn max_edges(&self) -> usize {
+        self.n_nodes * self.n_nodes.saturating_sub(1) / 2
+    }
+
+    /// The adjacency matrix of the graph, where `matrix[i][j]` is true if there
+    /// is an edge between `i` and `j`. The diagonal is always false.
+    pub fn to_adjacency_matrix(&self) -> Vec<Vec<bool>> {
+        self.conn
+            .iter()
+            .map(|nbs| (0..self.n_nodes).map(|j| nbs.contains(j)).collect())
+            .collect()
+    }
+
+    /// Create a graph from its adjacency matrix. The matrix must be square and
+    /// symmetric, with a false diagonal.
+    pub fn from_adjacency_matrix(matrix: &[Vec<bool>]) -> Result<Self, ShapeError> {
+        let n = matrix.len();
+        if let Some((row, values)) = matrix.iter().enumerate().find(|(_, r)| r.len() != n) {
+            return Err(ShapeError::NotSquare {
+                row,
+                len: values.len(),
+            });
+        }
+        let mut out = Self::new_empty(n);
+        for (i, row) in matrix.iter().enumerate() {
+            if row[i] {
+                return Err(ShapeError::SelfLoop(i));
+            }
+            for (j, &value) in row.iter().enumerate().skip(i + 1) {
+                if value != matrix[j][i] {
+                    return Err(ShapeError::NotSymmetric(i, j));
+                }
+                if value {
+                    out.add_edge(i as u32, j as u32);
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    /// Graph with the edges that are in either `self` or `other`. Both graphs
+    /// must have the same number of nodes.
+    pub fn union(&self, other: &Graph) -> Graph {
+        assert_eq!(self.n_nodes, other.n_nodes, "Graphs have different sizes");
+        let mut out = self.clone();
+        for (mine, theirs) in out.conn.iter_mut().zip(&other.conn) {
+            mine.union_with(theirs);
+        }
+        out.recount();
+        out
+    }
+
+    /// Graph with the edges of `self` that are not in `other`. Both graphs must
+    /// have the same number of nodes. Subtracting the edges of a cover leaves
+    /// the edges that remain to be covered.
+    pub fn difference(&self, other: &Graph) -> Graph {
+        assert_eq!(self.n_nodes, other.n_nodes, "Graphs have different sizes");
+        let mut out = self.clone();
+        for (mine, theirs) in out.conn.iter_mut().zip(&other.conn) {
+            mine.difference_with(theirs);
+        }
+        out.recount();
+        out
+    }
+
+    /// Recompute the cached edge counts after editing the bitsets directly.
+    fn recount(&mut self) {
+        for (valence, nbs) in self.valences.iter_mut().zip(&self.conn) {
+            *valence = nbs.count_ones(..);
+        }
+        self.remaining_edges = self.valences.iter().sum::<usize>() / 2;
+    }
+
+    /// The subgraph induced by `nodes`, i.e. with only the edges between these
+    /// nodes. Node `nodes[i]` is renumbered to `i` in the subgraph.
+    pub fn subgraph(&self, nodes: &[u32]) -> Graph {
+        let mut out = Graph::new_empty(nodes.len());
+        for (i, &a) in nodes.iter().enumerate() {
+            for (j, &b) in nodes.iter().enumerate().skip(i + 1) {
+                if self.has_edge(a, b) {
+                    out.add_edge(i as u32, j as u32);
+                }
+            }
+        }
+        out
+    }
+
+    /// Render the same matrix as `Display`, to show the progress of a solver
+    /// where `self` holds the remaining edges and `covered` the edges already
+    /// covered. When `color` is true, the remaining edges are shown in red, and
+    /// the covered edges that are not remaining are shown as dimmed dots, using
+    /// ANSI escape codes. Otherwise the output is the same as `Display`. Both
+    /// graphs must have the same number of nodes.
+    pub fn render_colored(&self, covered: &Graph, color: bool) -> String {
+        assert_eq!(self.n_nodes, covered.n_nodes, "Graphs have different sizes");
+        let mut out = String::new();
+        self.write_matrix(&mut out, false, color.then_some(covered))
+            .expect("Writing to a String cannot fail");
+        out
+    }
+
+    /// Render the same matrix as `Display`. When `full` is true, the complete
+    /// symmetric adjacency matrix is printed instead of just the lower
+    /// triangle, which is useful to spot asymmetric edges.
+    pub fn render(&self, full: bool) -> String {
+        let mut out = String::new();
+        self.write_matrix(&mut out, full, None)
+            .expect("Writing to a String cannot fail");
+        out
+    }
+
+    fn write_matrix<W: Write>(
+        &self,
+        f: &mut W,
+        full: bool,
+        covered: Option<&Graph>,
+    ) -> std::fmt::Result {
         writeln!(
             f,
             "Graph K_{} ({} edges remaining):",
@@ -101,20 +446,29 @@ impl Display for Graph {
             write!(f, "─")?;
         }
         writeln!(f, "┐")?;
-        // Print each row with row labels and borders (only bottom-right triangle)
+        // Print each row with row labels and borders (only bottom-right triangle,
+        // unless the full matrix is requested)
         for i in 0..(self.n_nodes as u32) {
             write!(f, "│{:width$}│", i, width = max_digits)?;
-            for j in 0..=i {
+            let ncols = if full { self.n_nodes as u32 } else { i + 1 };
+            for j in 0..ncols {
                 if i == j {
                     write!(f, " ")?; // Diagonal (self-loops don't exist)
                 } else if self.has_edge(i, j) {
-                    write!(f, "x")?; // Edge exists
+                    // Edge exists
+                    if covered.is_some() {
+                        write!(f, "\x1b[31mx\x1b[0m")?;
+                    } else {
+                        write!(f, "x")?;
+                    }
+                } else if covered.is_some_and(|c| c.has_edge(i, j)) {
+                    write!(f, "\x1b[2m·\x1b[0m")?; // Edge was covered
                 } else {
                     write!(f, " ")?; // No edge
                 }
             }
             // Fill remaining space to align with full width
-            for _j in (i + 1)..(self.n_nodes as u32) {
+            for _j in ncols..(self.n_nodes as u32) {
                 write!(f, " ")?;
             }
             writeln!(f, "│")?;
@@ -209,4 +563,358 @@ mod tests {
         graph.remove_edge(0, 1);
         assert!(graph.is_empty());
     }
+
+    #[test]
+    fn test_render_colored() {
+        let mut graph = Graph::new_complete(12);
+        graph.remove_edge(0, 1);
+        graph.remove_edge(5, 7);
+        let covered = Graph::new_complete(12).difference(&graph);
+        assert_eq!(graph.render_colored(&covered, false), format!("{}", graph));
+        let colored = graph.render_colored(&covered, true);
+        assert_eq!(colored.matches("\x1b[31mx").count(), graph.num_edges());
+        assert_eq!(colored.matches("\x1b[2m·").count(), 2);
+        // Edges that are neither remaining nor covered are left blank.
+        let mut partial = Graph::new_empty(12);
+        partial.add_edge(0, 1);
+        let colored = graph.render_colored(&partial, true);
+        assert_eq!(colored.matches("\x1b[2m·").count(), 1);
+        assert_eq!(
+            colored
+                .replace("\x1b[31mx\x1b[0m", "x")
+                .replace("\x1b[2m·\x1b[0m", " "),
+            format!("{}", graph)
+        );
+    }
+
+    #[test]
+    fn test_render_full_is_symmetric() {
+        let mut graph = Graph::new_complete(12);
+        graph.remove_edge(0, 1);
+        graph.remove_edge(5, 7);
+        graph.remove_edge(9, 11);
+        assert_eq!(graph.render(false), format!("{}", graph));
+        let full = graph.render(true);
+        let cells: Vec<Vec<char>> = full
+            .lines()
+            .skip(2)
+            .take(12)
+            .map(|line| line.split('│').nth(2).unwrap().chars().collect())
+            .collect();
+        for (i, row) in cells.iter().enumerate() {
+            for (j, &cell) in row.iter().enumerate() {
+                assert_eq!(cell, cells[j][i]);
+                assert_eq!(cell == 'x', graph.has_edge(i as u32, j as u32));
+            }
+        }
+    }
+
+    #[test]
+    fn test_remove_lattice() {
+        use crate::lattice::Direction;
+        let mut triangle = Lattice::new(3);
+        triangle.insert(0, Direction::RIGHT, 1);
+        triangle.insert(0, Direction::TOP_RIGHT, 2);
+        let mut graph = Graph::new_complete(3);
+        graph.remove_lattice(&triangle);
+        assert!(graph.is_empty());
+        let mut graph = Graph::new_complete(4);
+        graph.remove_lattice(&triangle);
+        assert_eq!(graph.num_edges(), 3);
+        assert_eq!(graph.valence(3), 3);
+    }
+
+    #[test]
+    fn test_find_candidates_incremental() {
+        let mut graph = Graph::new_complete(10);
+        for (i, j) in [(0, 3), (1, 4), (2, 5), (3, 6), (0, 9), (7, 8)] {
+            graph.remove_edge(i, j);
+        }
+        let required = [2, 0, 7, 1];
+        let mut batch = FixedBitSet::new();
+        let mut incremental = FixedBitSet::new();
+        graph.find_candidates(&required[..1], &mut incremental);
+        for k in 2..=required.len() {
+            graph.find_candidates_add(required[k - 1], &mut incremental);
+            graph.find_candidates(&required[..k], &mut batch);
+            assert_eq!(incremental, batch);
+        }
+        // Starting from no required nodes, every node is a candidate.
+        graph.find_candidates(&[], &mut incremental);
+        graph.find_candidates_add(2, &mut incremental);
+        graph.find_candidates(&[2], &mut batch);
+        assert_eq!(incremental, batch);
+    }
+
+    #[test]
+    fn test_cached_counts() {
+        use rand::{Rng, SeedableRng, rngs::StdRng};
+        let n = 12;
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut graph = Graph::new_complete(n);
+        for _ in 0..2000 {
+            let i = rng.random_range(0..n as u32);
+            let j = rng.random_range(0..n as u32);
+            if rng.random_bool(0.6) {
+                graph.remove_edge(i, j);
+            } else {
+                graph.add_edge(i, j);
+            }
+            // Compare the cached counts to a recount from the bitsets.
+            let valences: Vec<_> = graph.conn.iter().map(|c| c.count_ones(..)).collect();
+            let counted = valences.iter().sum::<usize>() / 2;
+            assert_eq!(graph.valences, valences);
+            assert_eq!(graph.remaining_edges, counted);
+            assert_eq!(graph.num_edges(), counted);
+            assert_eq!(graph.is_empty(), counted == 0);
+            let max = valences.iter().copied().max().unwrap();
+            let expected = valences.iter().position(|&v| v == max).map(|i| i as u32);
+            assert_eq!(graph.max_valence_node(), expected.filter(|_| max > 0));
+        }
+    }
+
+    #[test]
+    fn test_edge_counter() {
+        // Removing twice only counts once.
+        let mut graph = Graph::new_complete(3);
+        graph.remove_edge(0, 1);
+        graph.remove_edge(1, 0);
+        assert_eq!(graph.num_edges(), 2);
+        graph.add_edge(2, 2);
+        assert_eq!(graph.num_edges(), 2);
+        graph.remove_edge(0, 2);
+        graph.remove_edge(1, 2);
+        assert!(graph.is_empty());
+    }
+
+    #[test]
+    fn test_max_valence_node() {
+        // Every node of a complete graph has the same valence.
+        assert_eq!(Graph::new_complete(5).max_valence_node(), Some(0));
+        assert_eq!(Graph::new_complete(0).max_valence_node(), None);
+        assert_eq!(Graph::new_complete(1).max_valence_node(), None);
+        let mut graph = Graph::new_complete(6);
+        for (i, j) in [(0, 1), (0, 2), (1, 2), (1, 5), (2, 5), (3, 0), (4, 1)] {
+            graph.remove_edge(i, j);
+        }
+        assert_eq!(graph.max_valence_node(), Some(3));
+        let mut graph = Graph::new_complete(2);
+        graph.remove_edge(0, 1);
+        assert_eq!(graph.max_valence_node(), None);
+    }
+
+    #[test]
+    fn test_new_empty() {
+        let mut graph = Graph::new_empty(4);
+        assert!(graph.is_empty());
+        assert_eq!(graph.num_nodes(), 4);
+        assert_eq!(graph.max_valence_node(), None);
+        graph.add_edge(1, 3);
+        assert_eq!(graph.num_edges(), 1);
+        assert_eq!(graph.max_valence_node(), Some(1));
+    }
+
+    #[test]
+    fn test_subgraph() {
+        let sub = Graph::new_complete(5).subgraph(&[4, 1, 2]);
+        assert_eq!(sub.num_nodes(), 3);
+        assert_eq!(sub.num_edges(), 3);
+        let mut graph = Graph::new_complete(5);
+        graph.remove_edge(1, 4);
+        let sub = graph.subgraph(&[4, 1, 2]);
+        assert_eq!(sub.num_edges(), 2);
+        assert!(!sub.has_edge(0, 1));
+        assert!(sub.has_edge(0, 2));
+        assert!(sub.has_edge(1, 2));
+    }
+
+    #[test]
+    fn test_union_and_difference() {
+        use crate::lattice::Direction;
+        let mut lattice = Lattice::new(6);
+        lattice.insert(0, Direction::RIGHT, 1);
+        lattice.insert(0, Direction::TOP_RIGHT, 2);
+        lattice.insert(1, Direction::RIGHT, 4);
+        let covered = lattice.to_graph();
+        assert_eq!(covered.num_edges(), 4);
+        let mut expected = Graph::new_complete(6);
+        expected.remove_lattice(&lattice);
+        let remaining = Graph::new_complete(6).difference(&covered);
+        assert!(remaining == expected);
+        assert_eq!(remaining.num_edges(), 11);
+        assert_eq!(remaining.valence(1), 2);
+        assert!(remaining.union(&covered) == Graph::new_complete(6));
+        assert!(Graph::new_empty(6).union(&covered) == covered);
+    }
+
+    #[test]
+    fn test_remove_edges() {
+        let mut graph = Graph::new_complete(4);
+        graph.remove_edges([(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)]);
+        assert!(graph.is_empty());
+        let mut graph = Graph::new_complete(4);
+        graph.remove_edges(vec![(0, 1), (1, 0), (3, 2)]);
+        assert_eq!(graph.num_edges(), 4);
+        assert!(!graph.has_edge(2, 3));
+    }
+
+    #[test]
+    fn test_all_edges() {
+        let mut graph = Graph::new_complete(4);
+        assert_eq!(
+            graph.all_edges().collect::<Vec<_>>(),
+            vec![(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)]
+        );
+        graph.remove_edge(2, 1);
+        assert_eq!(graph.all_edges().count(), 5);
+        assert!(!graph.all_edges().any(|e| e == (1, 2)));
+        assert_eq!(Graph::new_empty(4).all_edges().count(), 0);
+    }
+
+    #[test]
+    fn test_new_random() {
+        assert!(Graph::new_random(7, 1.0, 3) == Graph::new_complete(7));
+        assert!(Graph::new_random(7, 0.0, 3) == Graph::new_empty(7));
+        let graph = Graph::new_random(20, 0.5, 42);
+        assert!(graph == Graph::new_random(20, 0.5, 42));
+        assert!(graph.num_edges() > 0 && graph.num_edges() < 190);
+        assert_eq!(
+            graph.num_edges(),
+            (0..20).map(|i| graph.valence(i)).sum::<usize>() / 2
+        );
+    }
+
+    #[test]
+    fn test_copy_into_reuses_buffers() {
+        let source = Graph::new_random(12, 0.5, 5);
+        let mut dst = Graph::new_complete(12);
+        let buffers: Vec<_> = dst.conn.iter().map(|row| row.as_slice().as_ptr()).collect();
+        source.copy_into(&mut dst);
+        assert!(dst == source);
+        assert_eq!(
+            dst.all_edges().collect::<Vec<_>>(),
+            source.all_edges().collect::<Vec<_>>()
+        );
+        assert_eq!(dst.num_edges(), source.num_edges());
+        assert!(
+            dst.conn
+                .iter()
+                .map(|row| row.as_slice().as_ptr())
+                .eq(buffers)
+        );
+        // Graphs of a different size still copy correctly.
+        let mut small = Graph::new_complete(3);
+        small.clone_from(&source);
+        assert!(small == source);
+    }
+
+    #[test]
+    fn test_neighbors() {
+        let graph = Graph::new_random(15, 0.4, 11);
+        for i in 0..15 {
+            let expected: Vec<u32> = (0..15).filter(|&j| graph.has_edge(i, j)).collect();
+            assert_eq!(graph.neighbors(i).collect::<Vec<_>>(), expected);
+            assert_eq!(expected.len(), graph.valence(i));
+        }
+    }
+
+    #[test]
+    fn test_new_complete_minus_matching() {
+        let graph = Graph::new_complete_minus_matching(6, &[(0, 1), (3, 2)]);
+        assert_eq!(graph.num_edges(), 13);
+        assert!(!graph.has_edge(1, 0));
+        assert!(!graph.has_edge(2, 3));
+        assert_eq!(graph.valence(0), 4);
+        assert_eq!(graph.valence(4), 5);
+    }
+
+    #[test]
+    fn test_valence_histogram() {
+        let mut graph = Graph::new_complete(4);
+        assert_eq!(graph.valence_histogram(), vec![0, 0, 0, 4]);
+        graph.remove_edge(0, 1);
+        assert_eq!(graph.valence_histogram(), vec![0, 0, 2, 2]);
+        assert_eq!(Graph::new_empty(3).valence_histogram(), vec![3]);
+        assert!(Graph::new_empty(0).valence_histogram().is_empty());
+    }
+
+    #[test]
+    fn test_is_complete_and_edge_density() {
+        let mut graph = Graph::new_complete(5);
+        assert!(graph.is_complete());
+        assert_eq!(graph.edge_density(), 1.0);
+        graph.remove_edge(1, 3);
+        assert!(!graph.is_complete());
+        assert_eq!(graph.edge_density(), 0.9);
+        let empty = Graph::new_empty(5);
+        assert!(!empty.is_complete());
+        assert_eq!(empty.edge_density(), 0.0);
+        assert_eq!(Graph::new_empty(1).edge_density(), 0.0);
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut graph = Graph::new_complete(5);
+        let capacity = graph.conn[0].len();
+        graph.clear();
+        assert!(graph.is_empty());
+        assert_eq!(graph.num_nodes(), 5);
+        assert_eq!(graph.conn[0].len(), capacity);
+        assert!(graph == Graph::new_empty(5));
+        assert_eq!(graph.max_valence_node(), None);
+        graph.add_edge(1, 3);
+        assert_eq!(graph.num_edges(), 1);
+    }
+
+    #[test]
+    fn test_to_adjacency_matrix() {
+        let mut graph = Graph::new_complete(5);
+        graph.remove_edge(1, 3);
+        let matrix = graph.to_adjacency_matrix();
+        assert_eq!(matrix.len(), 5);
+        for (i, row) in matrix.iter().enumerate() {
+            assert_eq!(row.len(), 5);
+            assert!(!row[i]);
+            for (j, &value) in row.iter().enumerate() {
+                assert_eq!(value, matrix[j][i]);
+                assert_eq!(value, graph.has_edge(i as u32, j as u32));
+            }
+        }
+        let count = matrix.iter().flatten().filter(|&&value| value).count();
+        assert_eq!(count, 2 * graph.num_edges());
+    }
+
+    #[test]
+    fn test_from_adjacency_matrix() {
+        let graph = Graph::new_complete(4);
+        let parsed = Graph::from_adjacency_matrix(&graph.to_adjacency_matrix()).unwrap();
+        assert!(parsed == graph);
+        let mut graph = Graph::new_random(9, 0.5, 1);
+        graph.remove_edge(0, 1);
+        let parsed = Graph::from_adjacency_matrix(&graph.to_adjacency_matrix()).unwrap();
+        assert!(parsed == graph);
+        assert!(Graph::from_adjacency_matrix(&[]).unwrap() == Graph::new_empty(0));
+    }
+
+    #[test]
+    fn test_from_adjacency_matrix_errors() {
+        let mut matrix = Graph::new_complete(4).to_adjacency_matrix();
+        matrix[2][0] = false;
+        assert_eq!(
+            Graph::from_adjacency_matrix(&matrix).err(),
+            Some(ShapeError::NotSymmetric(0, 2))
+        );
+        matrix[2][0] = true;
+        matrix[3][3] = true;
+        assert_eq!(
+            Graph::from_adjacency_matrix(&matrix).err(),
+            Some(ShapeError::SelfLoop(3))
+        );
+        matrix[3][3] = false;
+        matrix[1].pop();
+        assert_eq!(
+            Graph::from_adjacency_matrix(&matrix).err(),
+            Some(ShapeError::NotSquare { row: 1, len: 3 })
+        );
+    }
 }
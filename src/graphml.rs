@@ -0,0 +1,238 @@
+use std::{
+    collections::HashMap,
+    fmt::{Display, Write},
+};
+
+use crate::graph::{Graph, TGraph};
+
+/// Reasons why [`Graph::from_graphml`] could not read a graph.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GraphmlError {
+    /// The document has no `<graph>` element.
+    MissingGraph,
+    /// An element is missing one of its required attributes.
+    MissingAttribute {
+        element: &'static str,
+        attribute: &'static str,
+    },
+    /// More than one `<node>` has this id.
+    DuplicateNode(String),
+    /// An `<edge>` refers to a node id that was never declared.
+    UnknownNode(String),
+    /// The `<graph>` has `edgedefault="directed"`. Only undirected graphs can
+    /// be read.
+    Directed,
+}
+
+impl Display for GraphmlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GraphmlError::MissingGraph => write!(f, "No <graph> element found"),
+            GraphmlError::MissingAttribute { element, attribute } => {
+                write!(f, "<{element}> is missing the '{attribute}' attribute")
+            }
+            GraphmlError::DuplicateNode(id) => write!(f, "Node '{id}' is declared more than once"),
+            GraphmlError::UnknownNode(id) => write!(f, "Edge refers to unknown node '{id}'"),
+            GraphmlError::Directed => {
+                write!(f, "The graph is directed, expected an undirected one")
+            }
+        }
+    }
+}
+
+impl std::error::Error for GraphmlError {}
+
+/// Find the value of the attribute `name` in the contents of a tag.
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = tag;
+    while let Some(pos) = rest.find(name) {
+        let before = rest[..pos].chars().next_back();
+        let after = rest[(pos + name.len())..].trim_start();
+        rest = &rest[(pos + name.len())..];
+        if !before.is_some_and(char::is_whitespace) {
+            continue;
+        }
+        let Some(after) = after.strip_prefix('=') else {
+            continue;
+        };
+        let after = after.trim_start();
+        let quote = after.chars().next().filter(|&c| c == '"' || c == '\'')?;
+        let value = &after[1..];
+        return value.find(quote).map(|end| &value[..end]);
+    }
+    None
+}
+
+/// Remove the comments and CDATA sections from `xml`, so that the tags in them
+/// are not read as real ones. An unterminated section runs to the end.
+fn strip_comments(xml: &str) -> String {
+    let mut out = String::with_capacity(xml.len());
+    let mut rest = xml;
+    loop {
+        let next = [("<!--", "-->"), ("<![CDATA[", "]]>")]
+            .into_iter()
+            .filter_map(|(open, close)| rest.find(open).map(|pos| (pos, open, close)))
+            .min();
+        let Some((pos, open, close)) = next else {
+            out.push_str(rest);
+            return out;
+        };
+        out.push_str(&rest[..pos]);
+        let body = &rest[(pos + open.len())..];
+        rest = body
+            .find(close)
+            .map_or("", |end| &body[(end + close.len())..]);
+    }
+}
+
+impl Graph {
+    /// Write this graph as a GraphML document. Nodes are named `n0`, `n1` and
+    /// so on.
+    pub fn to_graphml(&self) -> String {
+        let mut out = String::new();
+        out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        out.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+        out.push_str("  <graph id=\"G\" edgedefault=\"undirected\">\n");
+        let n = self.num_nodes() as u32;
+        for i in 0..n {
+            writeln!(out, "    <node id=\"n{i}\"/>").expect("Writing to a String cannot fail");
+        }
+        for (i, j) in self.all_edges() {
+            writeln!(out, "    <edge source=\"n{i}\" target=\"n{j}\"/>")
+                .expect("Writing to a String cannot fail");
+        }
+        out.push_str("  </graph>\n</graphml>\n");
+        out
+    }
+
+    /// Read a graph from a GraphML document. Only the `<graph>`, `<node>` and
+    /// `<edge>` elements are read, everything else is ignored, including
+    /// comments and CDATA sections. The nodes are numbered `0..n` in the order
+    /// they are declared. Directed graphs are rejected.
+    pub fn from_graphml(xml: &str) -> Result<Graph, GraphmlError> {
+        let xml = strip_comments(xml);
+        let tags = || {
+            xml.split('<').skip(1).filter_map(|chunk| {
+                let tag = chunk.split('>').next()?.trim_end_matches('/');
+                let name_len = tag.find(|c: char| c.is_whitespace()).unwrap_or(tag.len());
+                Some((&tag[..name_len], tag))
+            })
+        };
+        let (_, graph_tag) = tags()
+            .find(|&(name, _)| name == "graph")
+            .ok_or(GraphmlError::MissingGraph)?;
+        if attribute(graph_tag, "edgedefault") == Some("directed") {
+            return Err(GraphmlError::Directed);
+        }
+        let mut ids = HashMap::new();
+        for (_, tag) in tags().filter(|&(name, _)| name == "node") {
+            let id = attribute(tag, "id").ok_or(GraphmlError::MissingAttribute {
+                element: "node",
+                attribute: "id",
+            })?;
+            let index = ids.len() as u32;
+            if ids.insert(id, index).is_some() {
+                return Err(GraphmlError::DuplicateNode(id.to_string()));
+            }
+        }
+        let mut graph = Graph::new_empty(ids.len());
+        for (_, tag) in tags().filter(|&(name, _)| name == "edge") {
+            let endpoint = |attr: &'static str| {
+                let id = attribute(tag, attr).ok_or(GraphmlError::MissingAttribute {
+                    element: "edge",
+                    attribute: attr,
+                })?;
+                ids.get(id)
+                    .copied()
+                    .ok_or_else(|| GraphmlError::UnknownNode(id.to_string()))
+            };
+            let source = endpoint("source")?;
+            let target = endpoint("target")?;
+            graph.add_edge(source, target);
+        }
+        Ok(graph)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn edge_set(graph: &Graph) -> Vec<(u32, u32)> {
+        graph.all_edges().collect()
+    }
+
+    #[test]
+    fn test_graphml_round_trip() {
+        let graph = Graph::new_complete(5);
+        let parsed = Graph::from_graphml(&graph.to_graphml()).unwrap();
+        assert_eq!(parsed.num_nodes(), 5);
+        assert_eq!(edge_set(&parsed), edge_set(&graph));
+        let mut graph = Graph::new_complete(5);
+        graph.remove_edge(1, 3);
+        graph.remove_edge(0, 4);
+        let parsed = Graph::from_graphml(&graph.to_graphml()).unwrap();
+        assert_eq!(edge_set(&parsed), edge_set(&graph));
+    }
+
+    #[test]
+    fn test_graphml_arbitrary_ids() {
+        let xml = r#"<graphml>
+            <graph edgedefault='undirected'>
+                <node id="a"/><node id="b"></node>
+                <node id = 'c' />
+                <edge id="e0" source="c" target="a"/>
+            </graph>
+        </graphml>"#;
+        let graph = Graph::from_graphml(xml).unwrap();
+        assert_eq!(graph.num_nodes(), 3);
+        assert_eq!(edge_set(&graph), vec![(0, 2)]);
+    }
+
+    #[test]
+    fn test_graphml_errors() {
+        assert_eq!(
+            Graph::from_graphml("<graphml></graphml>").err(),
+            Some(GraphmlError::MissingGraph)
+        );
+        assert_eq!(
+            Graph::from_graphml(r#"<graph><node id="a"/><edge source="a" target="b"/></graph>"#)
+                .err(),
+            Some(GraphmlError::UnknownNode("b".to_string()))
+        );
+        assert_eq!(
+            Graph::from_graphml(r#"<graph><node id="a"/><node id="a"/></graph>"#).err(),
+            Some(GraphmlError::DuplicateNode("a".to_string()))
+        );
+        assert_eq!(
+            Graph::from_graphml(r#"<graph edgedefault="directed"><node id="a"/></graph>"#).err(),
+            Some(GraphmlError::Directed)
+        );
+        assert_eq!(
+            Graph::from_graphml(r#"<graph><node/></graph>"#).err(),
+            Some(GraphmlError::MissingAttribute {
+                element: "node",
+                attribute: "id"
+            })
+        );
+    }
+
+    #[test]
+    fn test_graphml_skips_comments_and_cdata() {
+        let xml = r#"<graphml>
+            <!-- <graph edgedefault="directed"> -->
+            <graph edgedefault="undirected">
+                <node id="a"/><node id="b"/>
+                <!-- <node id="c"/> <edge source="a" target="c"/> -->
+                <data><![CDATA[<node id="d"/><edge source="a" target="b"/>]]></data>
+                <edge source="b" target="a"/>
+            </graph>
+        </graphml>"#;
+        let graph = Graph::from_graphml(xml).unwrap();
+        assert_eq!(graph.num_nodes(), 2);
+        assert_eq!(edge_set(&graph), vec![(0, 1)]);
+        // An unterminated comment hides the rest of the document.
+        let graph = Graph::from_graphml(r#"<graph><node id="a"/><!-- <node id="b"/>"#).unwrap();
+        assert_eq!(graph.num_nodes(), 1);
+    }
+}
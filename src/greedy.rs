@@ -1,83 +1,962 @@
 use fixedbitset::FixedBitSet;
+use rand::{
+    SeedableRng,
+    rngs::StdRng,
+    seq::{IteratorRandom, SliceRandom},
+};
 
 use crate::{
     graph::TGraph,
-    lattice::{Direction, Lattice, Neighbor},
+    lattice::{Direction, Lattice, Neighbor, SlotBuffers},
 };
 
-fn count_nbs(nbs: &[Neighbor; 6]) -> usize {
-    nbs.iter().filter_map(|n| n.get()).count()
+/// Decides the order in which the greedy solver tries to fill the empty slots
+/// of a lattice. Slots with higher scores are tried first.
+pub trait SlotHeuristic {
+    fn score(&self, lattice: &Lattice, slot: &(u32, Direction, [Neighbor; 6])) -> i64;
+}
+
+/// Try the most constrained slots first, i.e. the ones with the most filled
+/// neighbors.
+pub struct DefaultHeuristic;
+
+impl SlotHeuristic for DefaultHeuristic {
+    fn score(&self, _lattice: &Lattice, (_, _, nbs): &(u32, Direction, [Neighbor; 6])) -> i64 {
+        Neighbor::count_filled(nbs) as i64
+    }
 }
 
 pub fn solve_greedy<G>(num_nodes: usize) -> Vec<Lattice>
 where
     G: TGraph,
 {
+    solve_greedy_with::<G, _>(num_nodes, &DefaultHeuristic)
+}
+
+/// Same as `solve_greedy`, but covers the edges of `graph` instead of the
+/// complete graph.
+pub fn solve_greedy_graph<G>(graph: G) -> Vec<Lattice>
+where
+    G: TGraph,
+{
+    greedy_impl(
+        &mut Greedy::with_graph(graph, None, &DefaultHeuristic),
+        |_, _| {},
+    )
+}
+
+/// Same as `solve_greedy`, but uses `heuristic` to decide which empty slot to
+/// fill next.
+pub fn solve_greedy_with<G, H>(num_nodes: usize, heuristic: &H) -> Vec<Lattice>
+where
+    G: TGraph,
+    H: SlotHeuristic,
+{
+    greedy::<G, H>(num_nodes, (0, 1), heuristic)
+}
+
+/// Same as `solve_greedy`, but the first lattice starts from `seed_edge`
+/// instead of `(0, 1)`. Panics if `seed_edge` is not an edge of the complete
+/// graph with `num_nodes` nodes.
+pub fn solve_greedy_from<G>(num_nodes: usize, seed_edge: (u32, u32)) -> Vec<Lattice>
+where
+    G: TGraph,
+{
+    let (a, b) = seed_edge;
+    if num_nodes < 2 {
+        return Vec::new();
+    }
+    assert!(
+        a != b && a.max(b) < num_nodes as u32,
+        "({a}, {b}) is not an edge of the complete graph with {num_nodes} nodes"
+    );
+    greedy::<G, _>(num_nodes, seed_edge, &DefaultHeuristic)
+}
+
+/// Run the greedy solver `attempts` times and return the cover with the fewest
+/// lattices. Each attempt starts from a different edge of the complete graph,
+/// the first one from the same edge as `solve_greedy`. Once every edge has
+/// been tried, the later attempts also break ties randomly, like
+/// `solve_greedy_seeded` with the attempt index as the seed, so that no
+/// attempt repeats an earlier one. With the `rayon` feature, the attempts run
+/// in parallel.
+pub fn solve_greedy_best<G>(num_nodes: usize, attempts: usize) -> Vec<Lattice>
+where
+    G: TGraph,
+{
+    let n = num_nodes as u32;
+    let edges: Vec<_> = (0..n)
+        .flat_map(|i| ((i + 1)..n).map(move |j| (i, j)))
+        .collect();
+    if edges.is_empty() {
+        return solve_greedy::<G>(num_nodes);
+    }
+    let solve = |i: usize| {
+        let mut state = Greedy::new(num_nodes, edges[i % edges.len()], &DefaultHeuristic);
+        if i >= edges.len() {
+            state = state.with_rng(StdRng::seed_from_u64(i as u64));
+        }
+        (i, greedy_impl::<G, _, _>(&mut state, |_, _| {}))
+    };
+    // Break ties by the attempt index, so the result doesn't depend on the
+    // order in which the attempts finish.
+    let key = |(i, solution): &(usize, Vec<Lattice>)| (solution.len(), *i);
+    #[cfg(feature = "rayon")]
+    let best = {
+        use rayon::prelude::*;
+        (0..attempts.max(1))
+            .into_par_iter()
+            .map(solve)
+            .min_by_key(key)
+    };
+    #[cfg(not(feature = "rayon"))]
+    let best = (0..attempts.max(1)).map(solve).min_by_key(key);
+    best.expect("There is at least one attempt").1
+}
+
+/// Same as `solve_greedy`, but ties between equally good choices are broken
+/// using a random number generator seeded with `seed`. The same seed always
+/// produces the same cover, and different seeds explore different covers.
+pub fn solve_greedy_seeded<G>(num_nodes: usize, seed: u64) -> Vec<Lattice>
+where
+    G: TGraph,
+{
+    let mut state =
+        Greedy::new(num_nodes, (0, 1), &DefaultHeuristic).with_rng(StdRng::seed_from_u64(seed));
+    greedy_impl::<G, _, _>(&mut state, |_, _| {})
+}
+
+/// Same as `solve_greedy`, but calls `on_progress` with the number of
+/// remaining edges and the number of lattices found so far, every time a
+/// lattice is completed.
+pub fn solve_greedy_with_progress<G, F>(num_nodes: usize, mut on_progress: F) -> Vec<Lattice>
+where
+    G: TGraph,
+    F: FnMut(usize, usize),
+{
+    let mut state = Greedy::new(num_nodes, (0, 1), &DefaultHeuristic);
+    greedy_impl::<G, _, _>(&mut state, |graph, done| {
+        on_progress(graph.num_edges(), done.len())
+    })
+}
+
+/// Same as `solve_greedy`, but also returns the edges the solver left
+/// uncovered, as `(i, j)` pairs with `i < j`, in ascending order. The solver
+/// only stops early when it can't start a new lattice, which doesn't happen
+/// for complete graphs, so the list is expected to be empty. A non-empty list
+/// points at a bug in the solver.
+pub fn solve_greedy_checked<G>(num_nodes: usize) -> (Vec<Lattice>, Vec<(u32, u32)>)
+where
+    G: TGraph,
+{
+    let mut state = Greedy::<G, _>::new(num_nodes, (0, 1), &DefaultHeuristic);
+    let lattices = greedy_impl(&mut state, |_, _| {});
+    (lattices, state.graph.all_edges().collect())
+}
+
+/// Same as `solve_greedy`, but pairs each lattice with a copy of the edges
+/// that remain uncovered right after the lattice is completed.
+pub fn solve_greedy_trace<G>(num_nodes: usize) -> Vec<(Lattice, G)>
+where
+    G: TGraph,
+{
+    let mut state = Greedy::<G, _>::new(num_nodes, (0, 1), &DefaultHeuristic);
     let mut out = Vec::new();
-    let mut graph = G::new_complete(num_nodes);
-    let mut candidates = FixedBitSet::new();
-    let mut lattice = Lattice::new(num_nodes);
-    lattice.insert(0, Direction::RIGHT, 1);
-    graph.remove_edge(0, 1);
-    let mut latnbs = Vec::new();
-    let mut visitedbuf = Vec::new();
-    let mut slots = Vec::new();
-    while !graph.is_empty() {
-        lattice.empty_slots(&mut visitedbuf, &mut slots);
-        slots.sort_by(|(_, _, anbs), (_, _, bnbs)| count_nbs(anbs).cmp(&count_nbs(bnbs)));
-        let mut found = false;
+    while let Some(lattice) = state.next_lattice() {
+        out.push((lattice, state.graph.clone()));
+    }
+    out
+}
+
+/// Same as `solve_greedy`, but when filling a slot, prefers the node whose
+/// edges to the neighbors of the slot have the largest total `weight`. Ties are
+/// broken like `solve_greedy`, so uniform weights give the same cover.
+pub fn solve_greedy_weighted<G, W>(num_nodes: usize, weight: W) -> Vec<Lattice>
+where
+    G: TGraph,
+    W: Fn(u32, u32) -> u32,
+{
+    let mut state = Greedy::new(num_nodes, (0, 1), &DefaultHeuristic).with_weight(&weight);
+    greedy_impl::<G, _, _>(&mut state, |_, _| {})
+}
+
+/// Same as `solve_greedy`, but guarantees that no edge is covered by more than
+/// one lattice, i.e. the result is an edge-disjoint decomposition of the
+/// complete graph. The greedy covers are always disjoint, because a node is
+/// only inserted into a slot if it still has an edge to every neighbor of the
+/// slot, and those are exactly the edges the insert adds. So this is the same
+/// as `solve_greedy`, and only spells out the guarantee.
+pub fn solve_greedy_disjoint<G>(num_nodes: usize) -> Vec<Lattice>
+where
+    G: TGraph,
+{
+    solve_greedy::<G>(num_nodes)
+}
+
+/// Replace every lattice in `solutions` that has more than one connected
+/// component with one lattice per component, in the order of
+/// `Lattice::components`. Lattices with a single component are kept as they
+/// are, and empty lattices are dropped.
+pub fn split_components(solutions: Vec<Lattice>) -> Vec<Lattice> {
+    let mut out = Vec::with_capacity(solutions.len());
+    for lattice in solutions {
+        let components = lattice.components();
+        if components.len() == 1 {
+            out.push(lattice);
+        } else {
+            out.extend(components.iter().map(|nodes| lattice.component(nodes[0])));
+        }
+    }
+    out
+}
+
+/// Counters collected while running the greedy solver, to help profile the
+/// heuristic.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SolveStats {
+    /// Number of lattices in the solution.
+    pub lattices: usize,
+    /// Number of times the solver got stuck and started a new lattice.
+    pub restarts: usize,
+    /// Number of nodes inserted into lattices, including the first edge of
+    /// each lattice.
+    pub inserts: usize,
+    /// Number of empty slots for which candidates were looked up.
+    pub candidate_evaluations: usize,
+    /// Number of edges of the complete graph covered by the solution.
+    pub edges_covered: usize,
+}
+
+/// Same as `solve_greedy`, but produces the lattices lazily, each one as soon
+/// as it is completed.
+pub fn solve_greedy_iter<G>(num_nodes: usize) -> impl Iterator<Item = Lattice>
+where
+    G: TGraph,
+{
+    Greedy::<G, _>::new(num_nodes, (0, 1), &DefaultHeuristic)
+}
+
+/// Same as `solve_greedy`, but returns each lattice as just its edges, in the
+/// order of `Lattice::edges`. Plain edge lists are easier to hand over to
+/// JavaScript than lattices.
+#[cfg(feature = "wasm")]
+pub fn solve_to_edge_lists(num_nodes: usize) -> Vec<Vec<(u32, u32)>> {
+    solve_greedy_iter::<crate::graph::Graph>(num_nodes)
+        .map(|lattice| lattice.edges().collect())
+        .collect()
+}
+
+/// Runs `solve_greedy` many times, reusing the temporary buffers of the solver
+/// between runs instead of allocating them each time.
+pub struct GreedySolver<G> {
+    /// Graph of the previous run, reused when the next run has as many nodes.
+    graph: Option<G>,
+    lattice: Lattice,
+    candidates: FixedBitSet,
+    slotbufs: SlotBuffers,
+    slots: Vec<(u32, Direction, [Neighbor; 6])>,
+}
+
+impl<G> Default for GreedySolver<G>
+where
+    G: TGraph,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<G> GreedySolver<G>
+where
+    G: TGraph,
+{
+    pub fn new() -> Self {
+        Self {
+            graph: None,
+            lattice: Lattice::new(0),
+            candidates: FixedBitSet::new(),
+            slotbufs: SlotBuffers::default(),
+            slots: Vec::new(),
+        }
+    }
+
+    /// Same as `solve_greedy`.
+    pub fn solve(&mut self, num_nodes: usize) -> Vec<Lattice> {
+        let graph = match self.graph.take() {
+            Some(mut graph) if graph.num_nodes() == num_nodes => {
+                graph.clear();
+                for i in 0..(num_nodes as u32) {
+                    for j in (i + 1)..(num_nodes as u32) {
+                        graph.add_edge(i, j);
+                    }
+                }
+                graph
+            }
+            _ => G::new_complete(num_nodes),
+        };
+        let mut lattice = std::mem::replace(&mut self.lattice, Lattice::new(0));
+        if lattice.len() == num_nodes {
+            lattice.clear();
+        } else {
+            lattice = Lattice::new(num_nodes);
+        }
+        let mut state = Greedy::from_parts(graph, lattice, Some((0, 1)), &DefaultHeuristic);
+        state.candidates = std::mem::take(&mut self.candidates);
+        state.slotbufs = std::mem::take(&mut self.slotbufs);
+        state.slots = std::mem::take(&mut self.slots);
+        let out = greedy_impl(&mut state, |_, _| {});
+        self.candidates = state.candidates;
+        self.slotbufs = state.slotbufs;
+        self.slots = state.slots;
+        self.graph = Some(state.graph);
+        self.lattice = state.lattice;
+        out
+    }
+}
+
+/// A cover that was cut short by `solve_greedy_budget`.
+#[derive(Debug, Clone)]
+pub struct PartialSolve<G> {
+    /// The lattices built before running out of budget. The last one may be
+    /// unfinished.
+    pub lattices: Vec<Lattice>,
+    /// The edges not covered by `lattices`.
+    pub remaining: G,
+}
+
+/// Same as `solve_greedy`, but gives up once `max_inserts` nodes have been
+/// inserted into lattices, counting the first edge of each lattice as one
+/// insert. On giving up, returns the lattices built so far and the edges
+/// they don't cover.
+pub fn solve_greedy_budget<G>(
+    num_nodes: usize,
+    max_inserts: usize,
+) -> Result<Vec<Lattice>, PartialSolve<G>>
+where
+    G: TGraph,
+{
+    let mut state = Greedy::new(num_nodes, (0, 1), &DefaultHeuristic).with_max_inserts(max_inserts);
+    let lattices = greedy_impl::<G, _, _>(&mut state, |_, _| {});
+    if state.graph.is_empty() {
+        Ok(lattices)
+    } else {
+        Err(PartialSolve {
+            lattices,
+            remaining: state.graph,
+        })
+    }
+}
+
+/// Same as `solve_greedy`, but also returns counters collected while solving.
+pub fn solve_greedy_stats<G>(num_nodes: usize) -> (Vec<Lattice>, SolveStats)
+where
+    G: TGraph,
+{
+    let mut state = Greedy::new(num_nodes, (0, 1), &DefaultHeuristic);
+    let out = greedy_impl::<G, _, _>(&mut state, |_, _| {});
+    (out, state.stats)
+}
+
+/// The greedy solver, starting with a lattice that covers the `seed` edge.
+fn greedy<G, H>(num_nodes: usize, seed: (u32, u32), heuristic: &H) -> Vec<Lattice>
+where
+    G: TGraph,
+    H: SlotHeuristic,
+{
+    greedy_impl::<G, H, _>(&mut Greedy::new(num_nodes, seed, heuristic), |_, _| {})
+}
+
+/// Run `state` to completion. `on_lattice` is called with the remaining graph
+/// and the lattices completed so far, every time a lattice is completed.
+fn greedy_impl<G, H, F>(state: &mut Greedy<'_, G, H>, mut on_lattice: F) -> Vec<Lattice>
+where
+    G: TGraph,
+    H: SlotHeuristic,
+    F: FnMut(&G, &[Lattice]),
+{
+    let mut out = Vec::new();
+    while let Some(lattice) = state.next_lattice() {
+        out.push(lattice);
+        on_lattice(&state.graph, &out);
+    }
+    out
+}
+
+/// State of the greedy solver. Lattices are built one at a time, by adding one
+/// node at a time to the most promising empty slot, until no more nodes can be
+/// added.
+struct Greedy<'a, G, H> {
+    graph: G,
+    lattice: Lattice,
+    /// Edge to start the first lattice from.
+    seed: Option<(u32, u32)>,
+    heuristic: &'a H,
+    /// When present, ties between equally scored slots and between candidates
+    /// for a slot are broken randomly.
+    rng: Option<StdRng>,
+    candidates: FixedBitSet,
+    slotbufs: SlotBuffers,
+    slots: Vec<(u32, Direction, [Neighbor; 6])>,
+    stats: SolveStats,
+    /// Stop once this many nodes have been inserted.
+    max_inserts: usize,
+    /// When present, the candidate for a slot is the one whose edges to the
+    /// required nodes have the largest total weight.
+    weight: Option<&'a dyn Fn(u32, u32) -> u32>,
+}
+
+impl<G, H> Iterator for Greedy<'_, G, H>
+where
+    G: TGraph,
+    H: SlotHeuristic,
+{
+    type Item = Lattice;
+
+    fn next(&mut self) -> Option<Lattice> {
+        self.next_lattice()
+    }
+}
+
+impl<'a, G, H> Greedy<'a, G, H>
+where
+    G: TGraph,
+    H: SlotHeuristic,
+{
+    fn new(num_nodes: usize, seed: (u32, u32), heuristic: &'a H) -> Self {
+        Self::with_graph(G::new_complete(num_nodes), Some(seed), heuristic)
+    }
+
+    /// Solver that covers the edges of `graph`. Without a `seed`, the first
+    /// lattice starts like the later ones.
+    fn with_graph(graph: G, seed: Option<(u32, u32)>, heuristic: &'a H) -> Self {
+        let lattice = Lattice::new(graph.num_nodes());
+        Self::from_parts(graph, lattice, seed, heuristic)
+    }
+
+    /// Same as `with_graph`, but builds the lattices in `lattice`, which must
+    /// be empty and have room for all the nodes of `graph`.
+    fn from_parts(graph: G, lattice: Lattice, seed: Option<(u32, u32)>, heuristic: &'a H) -> Self {
+        Self {
+            lattice,
+            graph,
+            seed,
+            heuristic,
+            rng: None,
+            candidates: FixedBitSet::new(),
+            slotbufs: SlotBuffers::default(),
+            slots: Vec::new(),
+            stats: SolveStats::default(),
+            max_inserts: usize::MAX,
+            weight: None,
+        }
+    }
+
+    fn with_rng(mut self, rng: StdRng) -> Self {
+        self.rng = Some(rng);
+        self
+    }
+
+    fn with_max_inserts(mut self, max_inserts: usize) -> Self {
+        self.max_inserts = max_inserts;
+        self
+    }
+
+    fn with_weight(mut self, weight: &'a dyn Fn(u32, u32) -> u32) -> Self {
+        self.weight = Some(weight);
+        self
+    }
+
+    fn out_of_budget(&self) -> bool {
+        self.stats.inserts >= self.max_inserts
+    }
+
+    /// Build the next lattice, or return `None` if all edges are covered or
+    /// the insert budget is used up.
+    fn next_lattice(&mut self) -> Option<Lattice> {
+        if !self.start_lattice() {
+            return None;
+        }
+        while !self.graph.is_empty() && !self.out_of_budget() && self.grow() {}
+        let out = self.lattice.clone();
+        self.lattice.clear();
+        Some(out)
+    }
+
+    /// Start a new lattice with a single edge. The first lattice starts from the
+    /// seed edge, and the later ones from the node with the most remaining edges
+    /// and its neighbor with the most remaining edges. Returns false if there
+    /// are no edges left, or the insert budget is used up.
+    fn start_lattice(&mut self) -> bool {
+        if self.graph.is_empty() || self.out_of_budget() {
+            return false;
+        }
+        let graph = &self.graph;
+        let (a, b) = match self.seed.take() {
+            Some(seed) => seed,
+            None => {
+                let best = match graph.max_valence_node() {
+                    Some(best) => best,
+                    None => return false,
+                };
+                match graph.edges(best).fold(None, |nbest, current| {
+                    let cval = graph.valence(current);
+                    match nbest {
+                        Some((nbest, nval)) if nval >= cval => Some((nbest, nval)),
+                        _ => Some((current, cval)),
+                    }
+                }) {
+                    Some((nbest, _)) => (best, nbest),
+                    None => return false,
+                }
+            }
+        };
+        debug_assert!(
+            self.graph.has_edge(a, b),
+            "Edge ({a}, {b}) is already covered"
+        );
+        self.lattice.insert(a, Direction::RIGHT, b);
+        self.graph.remove_edge(a, b);
+        if self.stats.lattices > 0 {
+            self.stats.restarts += 1;
+        }
+        self.stats.lattices += 1;
+        self.stats.inserts += 1;
+        self.stats.edges_covered += 1;
+        true
+    }
+
+    /// Add one node to the current lattice. Returns false if no more nodes can
+    /// be added.
+    fn grow(&mut self) -> bool {
+        let Self {
+            graph,
+            lattice,
+            heuristic,
+            rng,
+            candidates,
+            slotbufs,
+            slots,
+            stats,
+            weight,
+            ..
+        } = self;
+        lattice.empty_slots_with(slotbufs, slots);
+        if let Some(rng) = rng.as_mut() {
+            // The sort is stable, so shuffling first breaks ties randomly.
+            slots.shuffle(rng);
+        }
+        slots.sort_by_key(|slot| heuristic.score(lattice, slot));
         while let Some((id, dir, nbs)) = slots.pop() {
-            latnbs.clear();
-            latnbs.extend(nbs.iter().filter_map(|n| n.get()));
-            if latnbs.is_empty() {
-                continue;
+            // Narrow down the candidates one required neighbor at a time.
+            let mut required = nbs.iter().filter_map(|n| n.get());
+            let first = match required.next() {
+                Some(first) => first,
+                None => continue,
+            };
+            graph.find_candidates(&[first], candidates);
+            for node in required {
+                graph.find_candidates_add(node, candidates);
             }
-            graph.find_candidates(&latnbs, &mut candidates);
-            let best = match candidates.ones().find(|i| !lattice.contains(*i as u32)) {
-                Some(i) => i as u32,
+            stats.candidate_evaluations += 1;
+            let mut choices = candidates
+                .ones()
+                .map(|i| i as u32)
+                .filter(|&i| !lattice.contains(i));
+            let best = match (*weight, rng.as_mut()) {
+                (Some(weight), _) => {
+                    // Keep the first of equally heavy candidates.
+                    let total = |c: u32| -> u64 {
+                        nbs.iter()
+                            .filter_map(|n| n.get())
+                            .map(|nb| weight(c, nb) as u64)
+                            .sum()
+                    };
+                    choices
+                        .map(|c| (c, total(c)))
+                        .fold(None, |best, (c, w)| match best {
+                            Some((_, bw)) if bw >= w => best,
+                            _ => Some((c, w)),
+                        })
+                        .map(|(c, _)| c)
+                }
+                (None, Some(rng)) => choices.choose(rng),
+                (None, None) => choices.next(),
+            };
+            let best = match best {
+                Some(best) => best,
                 None => continue,
             };
             lattice.insert(id, dir, best);
+            stats.inserts += 1;
+            // The insert links `best` to exactly the neighbors of the slot,
+            // and the candidates have an edge to all of them, so every edge it
+            // added is still uncovered and the cover stays disjoint.
+            #[cfg(debug_assertions)]
             for nb in lattice.neighbors(best) {
-                graph.remove_edge(best, nb);
+                assert!(
+                    graph.has_edge(best, nb),
+                    "Edge ({best}, {nb}) is already covered"
+                );
             }
-            found = true;
-            break;
+            let before = graph.num_edges();
+            graph.remove_edges(lattice.neighbors(best).map(|nb| (best, nb)));
+            stats.edges_covered += before - graph.num_edges();
+            return true;
         }
-        if !found {
-            out.push(lattice.clone());
-            lattice.clear();
-            match (0..(graph.num_nodes() as u32)).fold(
-                None,
-                |best: Option<(u32, usize)>, current| {
-                    let cval = graph.valence(current);
-                    match best {
-                        Some((best, val)) if val >= cval => Some((best, val)),
-                        _ => Some((current, cval)),
-                    }
-                },
-            ) {
-                Some((best, _)) => {
-                    match graph.edges(best).fold(None, |nbest, current| {
-                        let cval = graph.valence(current);
-                        match nbest {
-                            Some((nbest, nval)) if nval >= cval => Some((nbest, nval)),
-                            _ => Some((current, cval)),
-                        }
-                    }) {
-                        Some((nbest, _)) => {
-                            lattice.insert(best, Direction::RIGHT, nbest);
-                            graph.remove_edge(best, nbest);
-                        }
-                        None => break,
-                    }
-                }
-                None => break,
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        graph::Graph,
+        verify::{assert_covers, coverage_multiplicity, verify_cover},
+    };
+
+    /// Edges of each lattice, to compare covers.
+    fn edge_lists<'a>(lattices: impl IntoIterator<Item = &'a Lattice>) -> Vec<Vec<(u32, u32)>> {
+        lattices.into_iter().map(|l| l.edges().collect()).collect()
+    }
+
+    /// Opposite of the default, i.e. least constrained slots first.
+    struct ReverseHeuristic;
+
+    impl SlotHeuristic for ReverseHeuristic {
+        fn score(&self, lattice: &Lattice, slot: &(u32, Direction, [Neighbor; 6])) -> i64 {
+            -DefaultHeuristic.score(lattice, slot)
+        }
+    }
+
+    #[test]
+    fn test_greedy_covers() {
+        for n in 3..12 {
+            assert_covers(n, &solve_greedy::<Graph>(n));
+        }
+    }
+
+    #[test]
+    fn test_greedy_best_single_attempt() {
+        for n in 3..12 {
+            assert_eq!(
+                edge_lists(&solve_greedy_best::<Graph>(n, 1)),
+                edge_lists(&solve_greedy::<Graph>(n))
+            );
+        }
+    }
+
+    #[test]
+    fn test_greedy_best_covers() {
+        for n in 3..12 {
+            let best = solve_greedy_best::<Graph>(n, 8);
+            assert_covers(n, &best);
+            assert!(best.len() <= solve_greedy::<Graph>(n).len());
+        }
+    }
+
+    #[test]
+    fn test_greedy_best_more_attempts_than_edges() {
+        // K4 has 6 edges, so the attempts after those break ties randomly
+        // instead of repeating the same runs.
+        let n = 4;
+        let once = solve_greedy_best::<Graph>(n, 6);
+        let more = solve_greedy_best::<Graph>(n, 30);
+        assert_covers(n, &more);
+        assert!(more.len() <= once.len());
+        let seeded: Vec<_> = (6..30)
+            .map(|seed| edge_lists(&solve_greedy_seeded::<Graph>(n, seed)))
+            .collect();
+        assert!(seeded.iter().any(|cover| cover != &seeded[0]));
+    }
+
+    #[test]
+    fn test_greedy_iter() {
+        for n in 3..12 {
+            let actual: Vec<_> = solve_greedy_iter::<Graph>(n).collect();
+            assert_eq!(edge_lists(&actual), edge_lists(&solve_greedy::<Graph>(n)));
+        }
+        // Taking just the first lattice doesn't solve the rest.
+        assert_eq!(solve_greedy_iter::<Graph>(20).take(1).count(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "wasm")]
+    fn test_solve_to_edge_lists() {
+        for n in 3..12 {
+            let mut graph = Graph::new_complete(n);
+            for edges in solve_to_edge_lists(n) {
+                graph.remove_edges(edges);
             }
+            assert!(graph.is_empty());
         }
-        if graph.is_empty() {
-            out.push(lattice.clone());
+    }
+
+    #[test]
+    fn test_greedy_graph_minus_matching() {
+        use crate::verify::verify_cover_graph;
+        let graph = Graph::new_complete_minus_matching(6, &[(0, 1), (2, 3), (4, 5)]);
+        let solution = solve_greedy_graph(graph.clone());
+        assert_eq!(verify_cover_graph(&graph, &solution), Ok(()));
+        for lattice in &solution {
+            assert!(lattice.edges().all(|(a, b)| graph.has_edge(a, b)));
+        }
+        // A graph with no edges needs no lattices.
+        assert!(solve_greedy_graph(Graph::new_empty(4)).is_empty());
+    }
+
+    #[test]
+    fn test_greedy_budget() {
+        let n = 10;
+        let partial =
+            solve_greedy_budget::<Graph>(n, 5).expect_err("A budget of 5 inserts can't cover K10");
+        assert!(!partial.remaining.is_empty());
+        let mut graph = partial.remaining.clone();
+        for lattice in &partial.lattices {
+            graph.remove_lattice(lattice);
+        }
+        assert_eq!(graph.num_edges(), partial.remaining.num_edges());
+        let covered: usize = partial.lattices.iter().map(|l| l.edge_count()).sum();
+        assert_eq!(covered + partial.remaining.num_edges(), n * (n - 1) / 2);
+        let solution = solve_greedy_budget::<Graph>(n, usize::MAX)
+            .expect("An unlimited budget must cover K10");
+        assert_covers(n, &solution);
+        assert!(
+            solve_greedy_budget::<Graph>(n, 0)
+                .unwrap_err()
+                .lattices
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_greedy_solver_reuse() {
+        let mut solver = GreedySolver::<Graph>::new();
+        for n in [9, 9, 5, 12, 3] {
+            assert_eq!(
+                edge_lists(&solver.solve(n)),
+                edge_lists(&solve_greedy::<Graph>(n))
+            );
+        }
+    }
+
+    #[test]
+    fn test_greedy_never_double_covers() {
+        // In debug builds the solver checks that every edge it adds to a
+        // lattice is still uncovered, so these runs also exercise that check.
+        for n in 3..16 {
+            assert!(coverage_multiplicity(n, &solve_greedy::<Graph>(n)).is_empty());
+            assert!(coverage_multiplicity(n, &solve_greedy_seeded::<Graph>(n, 7)).is_empty());
+            assert!(coverage_multiplicity(n, &solve_greedy_best::<Graph>(n, 4)).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_greedy_trace() {
+        for n in 3..12 {
+            let trace = solve_greedy_trace::<Graph>(n);
+            assert_eq!(
+                edge_lists(trace.iter().map(|(l, _)| l)),
+                edge_lists(&solve_greedy::<Graph>(n))
+            );
+            let mut prev = n * (n - 1) / 2;
+            for (lattice, graph) in &trace {
+                assert_eq!(graph.num_edges(), prev - lattice.edge_count());
+                prev = graph.num_edges();
+            }
+            assert!(trace.last().unwrap().1.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_greedy_checked() {
+        for n in 0..16 {
+            let (solution, uncovered) = solve_greedy_checked::<Graph>(n);
+            assert!(uncovered.is_empty());
+            assert_eq!(solution.len(), solve_greedy::<Graph>(n).len());
+        }
+    }
+
+    #[test]
+    fn test_greedy_weighted() {
+        // Edges of `node` covered by the first lattice.
+        let covered_first = |solution: &[Lattice], node: u32| {
+            solution[0]
+                .edges()
+                .filter(|&(a, b)| a == node || b == node)
+                .count()
+        };
+        for n in 3..16 {
+            let plain = solve_greedy::<Graph>(n);
+            let uniform = solve_greedy_weighted::<Graph, _>(n, |_, _| 1);
+            assert_eq!(edge_lists(&uniform), edge_lists(&plain));
+            // Node 0 seeds the first lattice, and already wins ties, so
+            // weighting it can only keep its edges as early as before.
+            let heavy =
+                solve_greedy_weighted::<Graph, _>(n, |a, b| if a == 0 || b == 0 { 100 } else { 1 });
+            assert_covers(n, &heavy);
+            assert!(covered_first(&heavy, 0) >= covered_first(&plain, 0));
+        }
+        for n in 8..16 {
+            // The last node loses every tie by default, so weighting its edges
+            // pulls them into the first lattice.
+            let last = n as u32 - 1;
+            let plain = solve_greedy::<Graph>(n);
+            let heavy =
+                solve_greedy_weighted::<Graph, _>(
+                    n,
+                    |a, b| {
+                        if a == last || b == last { 100 } else { 1 }
+                    },
+                );
+            assert_covers(n, &heavy);
+            assert!(covered_first(&heavy, last) > covered_first(&plain, last));
+        }
+    }
+
+    #[test]
+    fn test_greedy_tiny() {
+        // Fewer than two nodes means there are no edges to cover, so the
+        // solver must not try to seed a lattice with the edge (0, 1).
+        for n in [0, 1] {
+            assert!(solve_greedy::<Graph>(n).is_empty());
+            assert!(solve_greedy_seeded::<Graph>(n, 3).is_empty());
+            assert!(solve_greedy_best::<Graph>(n, 4).is_empty());
+            assert!(solve_greedy_disjoint::<Graph>(n).is_empty());
+            assert!(solve_greedy_iter::<Graph>(n).next().is_none());
+            assert!(solve_greedy_trace::<Graph>(n).is_empty());
+            assert!(solve_greedy_stats::<Graph>(n).0.is_empty());
+            assert!(GreedySolver::<Graph>::new().solve(n).is_empty());
+            assert_eq!(verify_cover(n, &[]), Ok(()));
+        }
+        // Two nodes have a single edge, covered by a single lattice.
+        for solution in [
+            solve_greedy::<Graph>(2),
+            solve_greedy_seeded::<Graph>(2, 3),
+            solve_greedy_best::<Graph>(2, 4),
+            solve_greedy_disjoint::<Graph>(2),
+            solve_greedy_iter::<Graph>(2).collect(),
+        ] {
+            assert_eq!(solution.len(), 1);
+            assert_eq!(solution[0].edges().collect::<Vec<_>>(), vec![(0, 1)]);
+            assert_eq!(verify_cover(2, &solution), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_greedy_from() {
+        for n in 4..12 {
+            assert_eq!(
+                edge_lists(&solve_greedy_from::<Graph>(n, (0, 1))),
+                edge_lists(&solve_greedy::<Graph>(n))
+            );
+            let last = n as u32 - 1;
+            for seed in [(1, 2), (last, 0), (2, last)] {
+                let solution = solve_greedy_from::<Graph>(n, seed);
+                assert_eq!(verify_cover(n, &solution), Ok(()));
+                assert!(solution[0].contains_edge(seed.0, seed.1));
+            }
+        }
+        assert!(solve_greedy_from::<Graph>(1, (0, 1)).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "(2, 2) is not an edge")]
+    fn test_greedy_from_rejects_self_loop() {
+        solve_greedy_from::<Graph>(5, (2, 2));
+    }
+
+    #[test]
+    fn test_split_components() {
+        let mut lattice = Lattice::new(6);
+        lattice.insert(0, Direction::RIGHT, 1);
+        lattice.insert(0, Direction::TOP_RIGHT, 2);
+        lattice.insert(3, Direction::RIGHT, 4);
+        let mut single = Lattice::new(6);
+        single.insert(4, Direction::LEFT, 5);
+        let split = split_components(vec![lattice.clone(), single, Lattice::new(6)]);
+        assert_eq!(split.len(), 3);
+        assert!(split.iter().all(|l| l.components().len() == 1));
+        let mut edges: Vec<_> = split[..2].iter().flat_map(|l| l.edges()).collect();
+        edges.sort();
+        assert_eq!(edges, lattice.edges().collect::<Vec<_>>());
+        assert_eq!(split[2].edges().collect::<Vec<_>>(), vec![(4, 5)]);
+        for n in 3..16 {
+            assert_covers(n, &split_components(solve_greedy::<Graph>(n)));
+        }
+    }
+
+    #[test]
+    fn test_greedy_progress() {
+        for n in 3..12 {
+            let mut reports = Vec::new();
+            let solution = solve_greedy_with_progress::<Graph, _>(n, |remaining, done| {
+                reports.push((remaining, done))
+            });
+            assert_eq!(reports.len(), solution.len());
+            for (i, &(_, done)) in reports.iter().enumerate() {
+                assert_eq!(done, i + 1);
+            }
+            assert!(reports.windows(2).all(|w| w[0].0 >= w[1].0));
+            assert_eq!(reports.last().map(|&(remaining, _)| remaining), Some(0));
+        }
+    }
+
+    #[test]
+    fn test_greedy_seeded_reproducible() {
+        for n in 3..12 {
+            for seed in [0, 1, 42] {
+                assert_eq!(
+                    edge_lists(&solve_greedy_seeded::<Graph>(n, seed)),
+                    edge_lists(&solve_greedy_seeded::<Graph>(n, seed))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_greedy_seeded_covers() {
+        for n in 3..12 {
+            for seed in 0..8 {
+                assert_covers(n, &solve_greedy_seeded::<Graph>(n, seed));
+            }
+        }
+    }
+
+    #[test]
+    fn test_greedy_seeded_explores() {
+        let covers: Vec<_> = (0..8)
+            .map(|seed| edge_lists(&solve_greedy_seeded::<Graph>(9, seed)))
+            .collect();
+        assert!(covers.iter().any(|cover| *cover != covers[0]));
+    }
+
+    #[test]
+    fn test_greedy_stats() {
+        for n in 3..12 {
+            let (solution, stats) = solve_greedy_stats::<Graph>(n);
+            assert_covers(n, &solution);
+            assert_eq!(stats.edges_covered, n * (n - 1) / 2);
+            assert_eq!(stats.lattices, solution.len());
+            assert_eq!(stats.restarts, solution.len() - 1);
+            assert!(stats.inserts >= stats.lattices);
+            assert!(stats.candidate_evaluations >= stats.inserts - stats.lattices);
+        }
+    }
+
+    #[test]
+    fn test_greedy_disjoint() {
+        for n in 3..16 {
+            let solution = solve_greedy_disjoint::<Graph>(n);
+            assert_covers(n, &solution);
+            assert!(coverage_multiplicity(n, &solution).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_reverse_heuristic_covers() {
+        for n in 3..12 {
+            assert_covers(n, &solve_greedy_with::<Graph, _>(n, &ReverseHeuristic));
         }
     }
-    out
 }
@@ -1,21 +1,19 @@
 use std::{
+    collections::HashMap,
     fmt::{Debug, Display},
     num::NonZeroU32,
     ops::{Index, IndexMut},
+    str::FromStr,
 };
 
+use crate::graph::{Graph, TGraph};
+
 // Slot where a vertex maybe stored. The nonzerou32 stuff is to optimize the storage
 // for the two states when the vertex does and does not exist in the slot.
 #[repr(transparent)]
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Default)]
 pub struct Neighbor(Option<NonZeroU32>);
 
-impl Default for Neighbor {
-    fn default() -> Self {
-        Self(None)
-    }
-}
-
 impl Debug for Neighbor {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self.get() {
@@ -25,18 +23,59 @@ impl Debug for Neighbor {
     }
 }
 
+/// Error returned when trying to store `u32::MAX` in a [`Neighbor`]. That value
+/// is reserved to represent an empty slot.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct InvalidNodeId(pub u32);
+
+impl Display for InvalidNodeId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid node id: {}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidNodeId {}
+
 impl Neighbor {
+    /// Store `id` in this slot. `u32::MAX` cannot be stored, and will leave the
+    /// slot empty. Use `try_put` to catch that.
     fn put(&mut self, id: u32) {
         self.0 = NonZeroU32::new(id ^ u32::MAX);
     }
 
+    /// Same as `put`, but returns an error instead of silently clearing the
+    /// slot when `id` is `u32::MAX`.
+    pub fn try_put(&mut self, id: u32) -> Result<(), InvalidNodeId> {
+        if id == u32::MAX {
+            return Err(InvalidNodeId(id));
+        }
+        self.put(id);
+        Ok(())
+    }
+
     pub fn get(&self) -> Option<u32> {
         self.0.map(|v| v.get() ^ u32::MAX)
     }
 
+    /// Whether this slot holds a node.
+    pub fn is_filled(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Whether this slot is empty.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_none()
+    }
+
     fn clear(&mut self) {
         self.0 = None;
     }
+
+    /// Count the filled slots in `nbs`, e.g. to find the valence of an empty
+    /// slot reported by `Lattice::empty_slots`.
+    pub fn count_filled(nbs: &[Neighbor; 6]) -> usize {
+        nbs.iter().filter(|n| n.is_filled()).count()
+    }
 }
 
 #[derive(Copy, Clone, PartialEq, Eq)]
@@ -54,6 +93,54 @@ impl Display for Direction {
     }
 }
 
+/// Error returned when parsing a [`Direction`] from an unknown string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDirectionError(String);
+
+impl Display for ParseDirectionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Unknown direction: '{}'", self.0)
+    }
+}
+
+impl std::error::Error for ParseDirectionError {}
+
+impl FromStr for Direction {
+    type Err = ParseDirectionError;
+
+    /// Parse the names produced by `Display`, ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Direction::ALL_CCW
+            .into_iter()
+            .find(|dir| dir.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| ParseDirectionError(s.to_string()))
+    }
+}
+
+/// Error returned when converting an integer outside `0..6` into a [`Direction`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct InvalidDirection(pub u8);
+
+impl Display for InvalidDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid direction: {}. Expected a value in 0..6", self.0)
+    }
+}
+
+impl std::error::Error for InvalidDirection {}
+
+impl TryFrom<u8> for Direction {
+    type Error = InvalidDirection;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        if value < 6 {
+            Ok(Direction(value))
+        } else {
+            Err(InvalidDirection(value))
+        }
+    }
+}
+
 impl Direction {
     pub const RIGHT: Self = Self(0);
     pub const TOP_RIGHT: Self = Self(1);
@@ -71,21 +158,54 @@ impl Direction {
         Direction::BOTTOM_RIGHT,
     ];
 
-    const fn opposite(self) -> Self {
+    /// All six directions in counter clockwise order, starting from `RIGHT`.
+    pub fn all() -> impl Iterator<Item = Direction> {
+        Self::ALL_CCW.into_iter()
+    }
+
+    pub const fn opposite(self) -> Self {
         Self((self.0 + 3) % 6)
     }
 
+    /// Rotate by `steps` sixths of a full turn, counter clockwise for positive
+    /// `steps` and clockwise for negative `steps`.
+    pub const fn rotate_by(self, steps: i8) -> Self {
+        Self((self.0 as i8 + steps % 6).rem_euclid(6) as u8)
+    }
+
     const fn rotate_ccw(self) -> Self {
-        Self((self.0 + 1) % 6)
+        self.rotate_by(1)
     }
 
     const fn rotate_cw(self) -> Self {
-        Self((self.0 + 5) % 6)
+        self.rotate_by(-1)
     }
 
     const fn offset(&self) -> (isize, isize) {
         const OFFSETS: [(isize, isize); 6] = [(1, 0), (0, 1), (-1, 1), (-1, 0), (0, -1), (1, -1)];
-        return OFFSETS[self.0 as usize];
+        OFFSETS[self.0 as usize]
+    }
+
+    /// The direction from the axial coordinates `from` to the adjacent
+    /// coordinates `to`. Returns `None` if the coordinates are not adjacent.
+    pub fn between(from: (isize, isize), to: (isize, isize)) -> Option<Direction> {
+        let delta = (to.0 - from.0, to.1 - from.1);
+        Self::all().find(|dir| dir.offset() == delta)
+    }
+
+    /// The direction as a unit vector in screen space, where the lattice axes
+    /// are 60 degrees apart. `RIGHT` is `(1, 0)` and `TOP_RIGHT` is
+    /// `(0.5, sqrt(3) / 2)`.
+    pub fn to_unit_vector(&self) -> (f64, f64) {
+        let (dx, dy) = self.offset();
+        let (dx, dy) = (dx as f64, dy as f64);
+        (dx + 0.5 * dy, 0.75f64.sqrt() * dy)
+    }
+
+    /// Angle of the direction in degrees, counter clockwise from `RIGHT`, i.e.
+    /// `RIGHT` is 0, `TOP_RIGHT` is 60 and so on up to 300 for `BOTTOM_RIGHT`.
+    pub const fn angle_degrees(&self) -> u16 {
+        self.0 as u16 * 60
     }
 
     const fn as_str(&self) -> &str {
@@ -115,28 +235,128 @@ impl IndexMut<Direction> for [Neighbor; 6] {
     }
 }
 
-#[derive(Clone)]
+/// Reasons why [`Lattice::try_insert`] refused to insert a node.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum InsertError {
+    /// The slot in the requested direction from the source node already holds
+    /// this other node.
+    SourceOccupied(u32),
+    /// The slot of the new node facing back at the source node already holds
+    /// this other node.
+    DestinationOccupied(u32),
+    /// The source and the new node are the same node.
+    SelfLoop(u32),
+}
+
+impl Display for InsertError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InsertError::SourceOccupied(id) => write!(f, "Source slot is occupied by node {id}"),
+            InsertError::DestinationOccupied(id) => {
+                write!(f, "Destination slot is occupied by node {id}")
+            }
+            InsertError::SelfLoop(id) => write!(f, "Cannot insert node {id} next to itself"),
+        }
+    }
+}
+
+impl std::error::Error for InsertError {}
+
+/// Error returned by [`Lattice::from_graph`] when the edges of a graph don't
+/// form a single triangular lattice.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct NotALattice;
+
+impl Display for NotALattice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Graph cannot be laid out as a single triangular lattice")
+    }
+}
+
+impl std::error::Error for NotALattice {}
+
+/// Temporary buffers used by `Lattice::empty_slots_with`. Keep one around and
+/// pass it to every call, so that finding the empty slots doesn't allocate.
+#[derive(Default)]
+pub struct SlotBuffers {
+    visited: Vec<bool>,
+    stack: Vec<(u32, isize, isize)>,
+    nodes: Vec<(isize, isize, u32)>,
+    /// Component and grid position of each node, indexed by node id.
+    node_pos: Vec<(u32, isize, isize)>,
+    /// Nodes keyed by component and grid position, sorted for binary search.
+    positions: Vec<((u32, isize, isize), u32)>,
+}
+
+#[derive(Debug, Clone)]
 pub struct Lattice {
     conn: Box<[[Neighbor; 6]]>,
 }
 
 impl Lattice {
+    /// Create an empty lattice with room for `num_nodes` nodes. Node ids are
+    /// stored in `Neighbor` slots, which reserve `u32::MAX`, so the largest
+    /// supported node id is `u32::MAX - 1`.
     pub fn new(num_nodes: usize) -> Self {
+        debug_assert!(
+            num_nodes <= u32::MAX as usize,
+            "Lattice cannot have more than u32::MAX nodes"
+        );
         Self {
             conn: vec![Default::default(); num_nodes].into_boxed_slice(),
         }
     }
 
+    /// Create a lattice with room for `num_nodes` nodes, by applying the
+    /// `(id, dir, newid)` inserts in order. Panics if the resulting lattice is
+    /// not consistent.
+    pub fn with_edges(num_nodes: usize, inserts: &[(u32, Direction, u32)]) -> Self {
+        let mut lattice = Self::new(num_nodes);
+        for &(id, dir, newid) in inserts {
+            lattice.insert(id, dir, newid);
+        }
+        lattice.validate();
+        lattice
+    }
+
+    /// Number of node ids the lattice has room for, i.e. one more than the
+    /// largest valid id. This is not the number of nodes in the lattice, see
+    /// `iter_nodes` for those.
     pub fn len(&self) -> usize {
         self.conn.len()
     }
 
+    /// True if the lattice has no room for any node, i.e. `len() == 0`. Like
+    /// `len`, this is about capacity, so a lattice with room for nodes but
+    /// without any edges is not empty. This differs from `Graph::is_empty`,
+    /// which is true when the graph has no edges.
+    pub fn is_empty(&self) -> bool {
+        self.conn.is_empty()
+    }
+
+    /// Remove all the nodes and edges, keeping room for `len()` nodes. Use
+    /// `remove` to take out a single node, and `truncate` to shrink the lattice.
     pub fn clear(&mut self) {
         for nbs in &mut self.conn {
             nbs.fill(Neighbor::default());
         }
     }
 
+    /// Shrink the lattice to `new_len` nodes. The nodes with ids `>= new_len`
+    /// are removed first, so the remaining nodes lose their edges to them. Does
+    /// nothing if `new_len` is not less than `len()`.
+    pub fn truncate(&mut self, new_len: usize) {
+        if new_len >= self.len() {
+            return;
+        }
+        for id in (new_len as u32)..(self.len() as u32) {
+            self.remove(id);
+        }
+        let mut conn = std::mem::take(&mut self.conn).into_vec();
+        conn.truncate(new_len);
+        self.conn = conn.into_boxed_slice();
+    }
+
     fn step_loop_ccw(&self, node_id: u32, direction: Direction) -> Option<(u32, Direction, u8)> {
         let nb = self.neighbor(node_id, direction)?;
         let stop = direction.opposite();
@@ -167,14 +387,69 @@ impl Lattice {
         Some((nb, stop, 6))
     }
 
-    fn neighbor(&self, from: u32, dir: Direction) -> Option<u32> {
+    /// Step from `node` to its neighbor in direction `dir`, and turn counter
+    /// clockwise from the edge just walked, towards the next edge of that
+    /// neighbor. Returns the neighbor, the direction of that next edge, and
+    /// the number of sixths of a turn it took to reach it. One sixth means
+    /// the two edges are sides of a triangle, and six means the neighbor is a
+    /// dead end, so the walk turns back along `dir.opposite()`. Returns `None`
+    /// if `node` has no neighbor in direction `dir`.
+    ///
+    /// Repeated steps with the returned node and direction walk around a face
+    /// or a boundary loop of the lattice.
+    pub fn walk_ccw(&self, node: u32, dir: Direction) -> Option<(u32, Direction, u8)> {
+        self.step_loop_ccw(node, dir)
+    }
+
+    /// Same as `walk_ccw`, but turns clockwise.
+    pub fn walk_cw(&self, node: u32, dir: Direction) -> Option<(u32, Direction, u8)> {
+        self.step_loop_cw(node, dir)
+    }
+
+    pub(crate) fn neighbor(&self, from: u32, dir: Direction) -> Option<u32> {
         self.conn[from as usize][dir].get()
     }
 
+    /// The neighbor of `from` in direction `dir`, if any.
+    pub fn neighbor_in(&self, from: u32, dir: Direction) -> Option<u32> {
+        self.neighbor(from, dir)
+    }
+
+    /// Check if `a` and `b` are linked by an edge.
+    pub fn contains_edge(&self, a: u32, b: u32) -> bool {
+        self.neighbors(a).any(|nb| nb == b)
+    }
+
+    /// Nodes linked to both `a` and `b`. If `a` and `b` are linked, these are
+    /// the apexes of the triangles on either side of that edge.
+    pub fn common_neighbors(&self, a: u32, b: u32) -> impl Iterator<Item = u32> {
+        self.neighbors(a)
+            .filter(move |&nb| nb != b && self.contains_edge(b, nb))
+    }
+
     pub fn neighbors(&self, id: u32) -> impl Iterator<Item = u32> {
         self.conn[id as usize].iter().filter_map(|n| n.get())
     }
 
+    /// Number of filled neighbor slots of `id`, from 0 to 6.
+    pub fn degree(&self, id: u32) -> usize {
+        self.neighbors(id).count()
+    }
+
+    /// Iterate over the neighbors of `id`, along with their axial coordinates
+    /// when `id` is placed at `origin`.
+    pub fn neighbors_with_coords(
+        &self,
+        id: u32,
+        origin: (isize, isize),
+    ) -> impl Iterator<Item = (u32, isize, isize)> {
+        let (x, y) = origin;
+        self.neighbors_with_dirs(id).map(move |(nb, dir)| {
+            let (dx, dy) = dir.offset();
+            (nb, x + dx, y + dy)
+        })
+    }
+
     fn neighbors_with_dirs(&self, id: u32) -> impl Iterator<Item = (u32, Direction)> {
         self.conn[id as usize]
             .iter()
@@ -186,7 +461,238 @@ impl Lattice {
         self.neighbors(id).next().is_some()
     }
 
-    pub fn remove(&mut self, id: u32) {
+    /// Iterate over the ids of the nodes in the lattice, in ascending order.
+    pub fn iter_nodes(&self) -> impl Iterator<Item = u32> {
+        (0u32..(self.len() as u32)).filter(|&id| self.contains(id))
+    }
+
+    /// Iterate over all the edges in the lattice. Each edge is reported once,
+    /// as `(a, b)` with `a < b`. This holds even if the topology is broken and a
+    /// node has the same neighbor in more than one slot.
+    pub fn edges(&self) -> impl Iterator<Item = (u32, u32)> {
+        (0u32..(self.len() as u32)).flat_map(move |a| {
+            let nbs = &self.conn[a as usize];
+            nbs.iter().enumerate().filter_map(move |(i, nb)| {
+                let b = nb.get()?;
+                (a < b && !nbs[..i].iter().any(|prev| prev.get() == Some(b))).then_some((a, b))
+            })
+        })
+    }
+
+    /// Graph with the same nodes and edges as this lattice.
+    pub fn to_graph(&self) -> Graph {
+        let mut graph = Graph::new_empty(self.len());
+        for (a, b) in self.edges() {
+            graph.add_edge(a, b);
+        }
+        graph
+    }
+
+    /// Lay out the edges of `graph` as a single connected lattice, i.e. place
+    /// the nodes with edges on a triangular grid such that two nodes are at
+    /// adjacent positions if and only if they share an edge. Nodes without
+    /// edges are left out. This searches all layouts, so it can be slow for
+    /// large graphs that are not lattices.
+    pub fn from_graph(graph: &Graph) -> Result<Lattice, NotALattice> {
+        let n = graph.num_nodes();
+        let mut out = Lattice::new(n);
+        let Some(start) = (0..(n as u32)).find(|&id| graph.valence(id) > 0) else {
+            return Ok(out);
+        };
+        // Visit the nodes breadth first, so every node after the first has a
+        // neighbor that is placed before it.
+        let mut order = vec![start];
+        let mut seen = vec![false; n];
+        seen[start as usize] = true;
+        let mut i = 0;
+        while let Some(&id) = order.get(i) {
+            for nb in graph.edges(id) {
+                if !std::mem::replace(&mut seen[nb as usize], true) {
+                    order.push(nb);
+                }
+            }
+            i += 1;
+        }
+        if (0..(n as u32)).any(|id| graph.valence(id) > 0 && !seen[id as usize]) {
+            return Err(NotALattice);
+        }
+        let mut positions = vec![None; n];
+        let mut taken = HashMap::new();
+        positions[start as usize] = Some((0, 0));
+        taken.insert((0, 0), start);
+        if !Self::place_nodes(graph, &order[1..], &mut positions, &mut taken) {
+            return Err(NotALattice);
+        }
+        for (a, b) in graph.all_edges() {
+            let dir = Direction::between(
+                positions[a as usize].expect("All nodes are placed"),
+                positions[b as usize].expect("All nodes are placed"),
+            )
+            .expect("Neighbors are placed at adjacent positions");
+            out.conn[a as usize][dir].put(b);
+            out.conn[b as usize][dir.opposite()].put(a);
+        }
+        Ok(out)
+    }
+
+    /// Place `nodes` one at a time next to a neighbor that is already placed,
+    /// backtracking when a node has no consistent position. Returns false if
+    /// there is no layout.
+    fn place_nodes(
+        graph: &Graph,
+        nodes: &[u32],
+        positions: &mut [Option<(isize, isize)>],
+        taken: &mut HashMap<(isize, isize), u32>,
+    ) -> bool {
+        let Some((&node, rest)) = nodes.split_first() else {
+            return true;
+        };
+        let placed_nbs = graph
+            .edges(node)
+            .filter(|&nb| positions[nb as usize].is_some())
+            .count();
+        let (px, py) = graph
+            .edges(node)
+            .find_map(|nb| positions[nb as usize])
+            .expect("Nodes are placed in breadth first order");
+        for dir in Direction::all() {
+            let (dx, dy) = dir.offset();
+            let (x, y) = (px + dx, py + dy);
+            if taken.contains_key(&(x, y)) {
+                continue;
+            }
+            // Every node around this position must be a neighbor, and every
+            // placed neighbor must be around this position.
+            let mut adjacent = 0;
+            let consistent = Direction::all().all(|d| {
+                let (dx, dy) = d.offset();
+                match taken.get(&(x + dx, y + dy)) {
+                    Some(&other) => {
+                        adjacent += 1;
+                        graph.has_edge(node, other)
+                    }
+                    None => true,
+                }
+            });
+            if !consistent || adjacent != placed_nbs {
+                continue;
+            }
+            positions[node as usize] = Some((x, y));
+            taken.insert((x, y), node);
+            if Self::place_nodes(graph, rest, positions, taken) {
+                return true;
+            }
+            positions[node as usize] = None;
+            taken.remove(&(x, y));
+        }
+        false
+    }
+
+    /// Iterate over the triangular faces of the lattice. Each face is reported
+    /// once, as its three nodes in counter-clockwise order, starting with the
+    /// smallest.
+    pub fn faces(&self) -> impl Iterator<Item = [u32; 3]> {
+        (0u32..(self.len() as u32)).flat_map(move |a| {
+            Direction::all().filter_map(move |dir| {
+                let b = self.neighbor(a, dir)?;
+                let c = self.neighbor(a, dir.rotate_ccw())?;
+                (a < b && a < c).then_some([a, b, c])
+            })
+        })
+    }
+
+    /// Number of triangular faces, same as `faces().count()`. Each face is
+    /// found by stepping counter clockwise around the loop from an edge, and
+    /// counted from its smallest node.
+    pub fn triangle_count(&self) -> usize {
+        (0u32..(self.len() as u32))
+            .map(|a| {
+                self.neighbors_with_dirs(a)
+                    .filter(|&(b, dir)| match self.step_loop_ccw(a, dir) {
+                        Some((_, ndir, 1)) => {
+                            self.neighbor(b, ndir).is_some_and(|c| a < b && a < c)
+                        }
+                        _ => false,
+                    })
+                    .count()
+            })
+            .sum()
+    }
+
+    /// The faces of the lattice, in the order of `faces`, each along with the
+    /// sorted indices of the faces that share an edge with it.
+    pub fn face_adjacency(&self) -> Vec<([u32; 3], Vec<usize>)> {
+        let faces: Vec<_> = self.faces().collect();
+        let mut edge_faces: HashMap<(u32, u32), Vec<usize>> = HashMap::new();
+        let face_edges =
+            |[a, b, c]: [u32; 3]| [(a, b), (b, c), (c, a)].map(|(p, q)| (p.min(q), p.max(q)));
+        for (fi, &face) in faces.iter().enumerate() {
+            for edge in face_edges(face) {
+                edge_faces.entry(edge).or_default().push(fi);
+            }
+        }
+        faces
+            .iter()
+            .enumerate()
+            .map(|(fi, &face)| {
+                let mut adjacent: Vec<usize> = face_edges(face)
+                    .iter()
+                    .flat_map(|edge| &edge_faces[edge])
+                    .copied()
+                    .filter(|&other| other != fi)
+                    .collect();
+                adjacent.sort_unstable();
+                adjacent.dedup();
+                (face, adjacent)
+            })
+            .collect()
+    }
+
+    /// Number of edges in the lattice, counted from `edges`. See `num_edges`
+    /// for a cheaper count that relies on the lattice not being broken.
+    pub fn edge_count(&self) -> usize {
+        self.edges().count()
+    }
+
+    /// Number of edges in the lattice, i.e. half the sum of the degrees of the
+    /// nodes. This is cheaper than counting `edges`, but unlike `edges`, it
+    /// counts a neighbor twice if a broken lattice has it in two slots.
+    pub fn num_edges(&self) -> usize {
+        self.iter_nodes().map(|id| self.degree(id)).sum::<usize>() / 2
+    }
+
+    /// A lattice is triangulated when every edge is a side of at least one
+    /// triangle, i.e. the lattice is a union of triangles with no dangling
+    /// edges. A single edge is not triangulated. A lattice without any edges is
+    /// trivially triangulated.
+    pub fn is_triangulated(&self) -> bool {
+        self.conn.iter().all(|nbs| {
+            Direction::all().all(|dir| {
+                nbs[dir].is_empty()
+                    || nbs[dir.rotate_ccw()].is_filled()
+                    || nbs[dir.rotate_cw()].is_filled()
+            })
+        })
+    }
+
+    /// A node is interior when all six of its neighbor slots are filled, i.e.
+    /// it is fully surrounded by triangles.
+    pub fn is_interior(&self, id: u32) -> bool {
+        self.conn[id as usize].iter().all(|n| n.is_filled())
+    }
+
+    pub fn interior_nodes(&self) -> impl Iterator<Item = u32> {
+        (0u32..(self.len() as u32)).filter(|&id| self.is_interior(id))
+    }
+
+    /// Nodes that are present in the lattice, but not fully surrounded.
+    pub fn boundary_nodes(&self) -> impl Iterator<Item = u32> {
+        self.iter_nodes().filter(|&id| !self.is_interior(id))
+    }
+
+    /// Remove a node and all its edges from the lattice. Returns `true` if the
+    /// node was in the lattice, i.e. at least one edge was removed.
+    pub fn remove(&mut self, id: u32) -> bool {
         let mut nbs = [u32::MAX; 6];
         let mut dirs = [Direction::RIGHT; 6];
         let mut count = 0usize;
@@ -202,9 +708,50 @@ impl Lattice {
             self.conn[id as usize][dir].clear();
             self.conn[nb as usize][dir.opposite()].clear();
         }
+        count > 0
+    }
+
+    /// Same as `insert`, except this refuses to displace any existing nodes.
+    /// An error is returned if the slot in direction `dir` from `id`, or the
+    /// slot of `newid` facing back at `id`, is occupied by a different node.
+    /// Unlike `insert`, which ignores it, inserting a node next to itself is
+    /// also an error.
+    pub fn try_insert(&mut self, id: u32, dir: Direction, newid: u32) -> Result<(), InsertError> {
+        if id == newid {
+            return Err(InsertError::SelfLoop(id));
+        }
+        match self.neighbor(id, dir) {
+            Some(nb) if nb == newid => return Ok(()), // Already linked.
+            Some(nb) => return Err(InsertError::SourceOccupied(nb)),
+            None => {}
+        }
+        if let Some(nb) = self.neighbor(newid, dir.opposite()) {
+            return Err(InsertError::DestinationOccupied(nb));
+        }
+        self.insert(id, dir, newid);
+        Ok(())
+    }
+
+    /// Check if inserting `newid` in direction `dir` from `id` would succeed
+    /// without displacing any existing nodes, i.e. `id` and `newid` are
+    /// different, and the slot in direction `dir` from `id` and the slot of
+    /// `newid` facing back at `id` are both empty.
+    pub fn can_insert(&self, id: u32, dir: Direction, newid: u32) -> bool {
+        id != newid
+            && self.neighbor(id, dir).is_none()
+            && self.neighbor(newid, dir.opposite()).is_none()
     }
 
     pub fn insert(&mut self, id: u32, dir: Direction, newid: u32) {
+        self.insert_unchecked(id, dir, newid);
+        // Catch broken topology at the insert that caused it, rather than at a
+        // later call to `validate`.
+        #[cfg(debug_assertions)]
+        self.validate_around(newid);
+    }
+
+    /// Same as `insert`, but without any checks, even in debug builds.
+    fn insert_unchecked(&mut self, id: u32, dir: Direction, newid: u32) {
         if id == newid {
             return;
         }
@@ -215,26 +762,34 @@ impl Lattice {
         // Now insert.
         self.conn[id as usize][dir].put(newid);
         self.conn[newid as usize][dir.opposite()].put(id);
+        // Set when the orbit comes back around to `id`, i.e. the new node
+        // filled a hole with a full ring of neighbors around it.
+        let mut closed = false;
         {
             // Orbit the loop clockwise and link nodes.
+            let start = id;
             let mut id = id;
             let mut dir = dir.rotate_ccw();
             while let Some(next) = self.neighbor(id, dir) {
-                dir = dir.opposite().rotate_ccw();
+                if next == start {
+                    closed = true;
+                    break;
+                }
+                dir = dir.rotate_by(4);
                 self.conn[next as usize][dir].put(newid);
                 self.conn[newid as usize][dir.opposite()].put(next);
                 dir = dir.rotate_ccw();
                 id = next;
             }
         }
-        {
+        if !closed {
             // Orbit the loop counter clock wise direction.
             // This may not be required depending on how far the other loop went, but leaving this in for now.
             // Will think about it if it becomes a bottlneck.
             let mut id = id;
             let mut dir = dir.rotate_cw();
             while let Some(next) = self.neighbor(id, dir) {
-                dir = dir.opposite().rotate_cw();
+                dir = dir.rotate_by(2);
                 self.conn[next as usize][dir].put(newid);
                 self.conn[newid as usize][dir.opposite()].put(next);
                 dir = dir.rotate_cw();
@@ -243,15 +798,338 @@ impl Lattice {
         }
     }
 
+    /// Alias of `insert`, for callers that want to spell out that the insert
+    /// is checked. Like `insert`, debug builds check the nodes touched by the
+    /// insert, and panic if they are broken. This only looks at `newid` and its
+    /// neighbors, so it is much cheaper than calling `validate` after every
+    /// insert. Release builds skip the check.
+    pub fn insert_validated(&mut self, id: u32, dir: Direction, newid: u32) {
+        self.insert(id, dir, newid);
+    }
+
+    /// Insert a path of nodes, starting at `start`. Each `(dir, newid)` step
+    /// inserts `newid` in direction `dir` from the node inserted by the
+    /// previous step.
+    pub fn insert_path(&mut self, start: u32, steps: &[(Direction, u32)]) {
+        steps.iter().fold(start, |id, &(dir, newid)| {
+            self.insert(id, dir, newid);
+            newid
+        });
+    }
+
+    /// Check the lattice for consistency, and panic if any of the invariants are violated.
+    pub fn validate(&self) {
+        for node in 0u32..(self.len() as u32) {
+            // Skip empty nodes
+            if !self.contains(node) {
+                continue;
+            }
+            self.validate_node(node);
+        }
+    }
+
+    /// Check the invariants of `node` and its neighbors, i.e. the nodes whose
+    /// links change when `node` is inserted.
+    #[cfg(debug_assertions)]
+    fn validate_around(&self, node: u32) {
+        for node in std::iter::once(node).chain(self.neighbors(node)) {
+            self.validate_node(node);
+        }
+    }
+
+    /// Check the invariants of a single node, and panic if any are violated.
+    fn validate_node(&self, node: u32) {
+        // Check bidirectional connections
+        for dir in Direction::ALL_CCW {
+            if let Some(neighbor_id) = self.neighbor(node, dir) {
+                // Verify neighbor points back to this node
+                let back_neighbor = self.neighbor(neighbor_id, dir.opposite());
+                assert_eq!(
+                    back_neighbor,
+                    Some(node),
+                    "Node {} has neighbor {} in direction {:?}, but neighbor {} doesn't point back (has {:?} instead of Some({}))",
+                    node,
+                    neighbor_id,
+                    dir,
+                    neighbor_id,
+                    back_neighbor,
+                    node
+                );
+                // Verify neighbor exists in lattice
+                assert!(
+                    self.contains(neighbor_id),
+                    "Node {} has neighbor {} in direction {:?}, but neighbor {} doesn't exist in lattice",
+                    node,
+                    neighbor_id,
+                    dir,
+                    neighbor_id
+                );
+            }
+        }
+        // Check triangular loops using step_loop functions
+        for (_, dir) in self.neighbors_with_dirs(node) {
+            let cw = (0..3).try_fold((node, dir), |(id, dir), _| {
+                match self.step_loop_cw(id, dir)? {
+                    (next, ndir, 1) => Some((next, ndir)),
+                    _ => None,
+                }
+            });
+            if let Some((last, _)) = cw {
+                assert_eq!(last, node);
+            }
+            let ccw = (0..3).try_fold((node, dir), |(id, dir), _| {
+                match self.step_loop_ccw(id, dir)? {
+                    (next, ndir, 1) => Some((next, ndir)),
+                    _ => None,
+                }
+            });
+            if let Some((last, _)) = ccw {
+                assert_eq!(last, node);
+            }
+        }
+        // Check that no node references itself as a neighbor
+        for neighbor in self.neighbors(node) {
+            assert_ne!(neighbor, node, "Node {} has itself as a neighbor", node);
+        }
+        // Check that no neighbor is linked in more than one slot
+        for (i, a) in self.neighbors(node).enumerate() {
+            assert!(
+                !self.neighbors(node).skip(i + 1).any(|b| b == a),
+                "Node {} has neighbor {} in more than one slot",
+                node,
+                a
+            );
+        }
+    }
+
+    /// Walk the connected component containing `start` and collect its nodes
+    /// along with their axial coordinates, with `start` placed at the
+    /// origin. Nodes are marked in `visited` as they're reached. `stack` is a
+    /// temporary buffer passed in by the caller to avoid allocations.
+    fn walk_component(
+        &self,
+        start: u32,
+        visited: &mut [bool],
+        stack: &mut Vec<(u32, isize, isize)>,
+        out: &mut Vec<(isize, isize, u32)>,
+    ) {
+        out.clear();
+        stack.clear();
+        visited[start as usize] = true;
+        stack.push((start, 0isize, 0isize));
+        while let Some((node, x, y)) = stack.pop() {
+            out.push((x, y, node));
+            for (neighbor, nx, ny) in self.neighbors_with_coords(node, (x, y)) {
+                if !std::mem::replace(&mut visited[neighbor as usize], true) {
+                    stack.push((neighbor, nx, ny));
+                }
+            }
+        }
+    }
+
+    /// Group the nodes of this lattice by connected component. The components
+    /// are ordered by their smallest node, and the nodes within each component
+    /// are sorted.
+    pub fn components(&self) -> Vec<Vec<u32>> {
+        let mut visited = vec![false; self.len()];
+        let mut stack = Vec::new();
+        let mut nodes = Vec::new();
+        let mut out = Vec::new();
+        for start in 0u32..(self.len() as u32) {
+            if visited[start as usize] || !self.contains(start) {
+                continue;
+            }
+            self.walk_component(start, &mut visited, &mut stack, &mut nodes);
+            let mut component: Vec<u32> = nodes.iter().map(|&(_, _, node)| node).collect();
+            component.sort();
+            out.push(component);
+        }
+        out
+    }
+
+    /// Get the connected component containing `start` as its own lattice. The
+    /// node ids are preserved, so the returned lattice has the same `len()`.
+    pub fn component(&self, start: u32) -> Lattice {
+        let mut out = Lattice::new(self.len());
+        if !self.contains(start) {
+            return out;
+        }
+        let mut visited = vec![false; self.len()];
+        let mut nodes = Vec::new();
+        self.walk_component(start, &mut visited, &mut Vec::new(), &mut nodes);
+        for &(_, _, node) in &nodes {
+            out.conn[node as usize] = self.conn[node as usize];
+        }
+        out
+    }
+
+    /// Euler characteristic `V - E + F` of the connected component containing
+    /// `start`, counting the triangular faces. This is 1 for a triangulated
+    /// disk, and 0 if `start` is not in the lattice.
+    pub fn euler_characteristic(&self, start: u32) -> isize {
+        let component = self.component(start);
+        let vertices = component.iter_nodes().count();
+        vertices as isize - component.edges().count() as isize + component.faces().count() as isize
+    }
+
+    /// Copy of this lattice with only room for the nodes it contains, renumbered
+    /// in ascending order of their ids. Also returns the old id of each new
+    /// node.
+    pub fn compacted(&self) -> (Lattice, Vec<u32>) {
+        let old_ids: Vec<u32> = self.iter_nodes().collect();
+        let mut new_ids = vec![None; self.len()];
+        for (new, &old) in old_ids.iter().enumerate() {
+            new_ids[old as usize] = Some(new as u32);
+        }
+        let mut out = Lattice::new(old_ids.len());
+        for (slots, &old) in out.conn.iter_mut().zip(&old_ids) {
+            for dir in Direction::all() {
+                if let Some(nb) = self.conn[old as usize][dir]
+                    .get()
+                    .and_then(|nb| new_ids[nb as usize])
+                {
+                    slots[dir].put(nb);
+                }
+            }
+        }
+        (out, old_ids)
+    }
+
+    /// Copy of this lattice with every node `i` renamed to `perm[i]`, and the
+    /// same links between them. Panics if `perm` is not a permutation of
+    /// `0..self.len()`.
+    pub fn apply_permutation(&self, perm: &[u32]) -> Lattice {
+        assert_eq!(
+            perm.len(),
+            self.len(),
+            "The permutation must have one entry per node"
+        );
+        let mut seen = vec![false; perm.len()];
+        for &p in perm {
+            assert!(
+                (p as usize) < perm.len() && !std::mem::replace(&mut seen[p as usize], true),
+                "{perm:?} is not a permutation of 0..{}",
+                perm.len()
+            );
+        }
+        let mut out = Lattice::new(self.len());
+        for (old, slots) in self.conn.iter().enumerate() {
+            for dir in Direction::all() {
+                if let Some(nb) = slots[dir].get() {
+                    out.conn[perm[old] as usize][dir].put(perm[nb as usize]);
+                }
+            }
+        }
+        out
+    }
+
+    /// Find the node at axial coordinates `(x, y)`, in the connected component
+    /// containing `component_start`, with `component_start` placed at the
+    /// origin. Returns `None` if that position is empty, or if
+    /// `component_start` is not in the lattice.
+    pub fn node_at(&self, component_start: u32, x: isize, y: isize) -> Option<u32> {
+        if !self.contains(component_start) {
+            return None;
+        }
+        let mut visited = vec![false; self.len()];
+        let mut nodes = Vec::new();
+        self.walk_component(component_start, &mut visited, &mut Vec::new(), &mut nodes);
+        nodes
+            .into_iter()
+            .find_map(|(nx, ny, node)| (nx == x && ny == y).then_some(node))
+    }
+
+    /// Find a pair of nodes that prevent this lattice from being laid out on a
+    /// triangular grid. That is either two nodes landing at the same position,
+    /// or two nodes at adjacent positions that are not linked to each other.
+    fn find_layout_conflict(&self) -> Option<(u32, u32)> {
+        let mut visited = vec![false; self.len()];
+        let mut stack = Vec::new();
+        let mut nodes = Vec::new();
+        let mut positions = HashMap::new();
+        for start in 0u32..(self.len() as u32) {
+            if visited[start as usize] || !self.contains(start) {
+                continue;
+            }
+            self.walk_component(start, &mut visited, &mut stack, &mut nodes);
+            positions.clear();
+            for &(x, y, node) in &nodes {
+                if let Some(other) = positions.insert((x, y), node) {
+                    return Some((other, node));
+                }
+            }
+            for &(x, y, node) in &nodes {
+                for dir in Direction::ALL_CCW {
+                    let (dx, dy) = dir.offset();
+                    let expected = positions.get(&(x + dx, y + dy)).copied();
+                    let actual = self.neighbor(node, dir);
+                    if expected != actual {
+                        return Some((node, expected.or(actual).unwrap_or(node)));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Add all the edges of `other` to this lattice, keeping the directions
+    /// they have in `other`. Both lattices must have the same number of
+    /// nodes. This panics if the combined edges cannot be laid out as a
+    /// triangular lattice.
+    pub fn merge(&mut self, other: &Lattice) {
+        assert_eq!(
+            self.len(),
+            other.len(),
+            "Cannot merge lattices with different number of nodes"
+        );
+        for id in 0u32..(other.len() as u32) {
+            for (nb, dir) in other.neighbors_with_dirs(id) {
+                match (self.neighbor(id, dir), self.neighbor(nb, dir.opposite())) {
+                    (Some(a), Some(b)) if a == nb && b == id => {} // Already linked.
+                    (None, None) => {
+                        self.conn[id as usize][dir].put(nb);
+                        self.conn[nb as usize][dir.opposite()].put(id);
+                    }
+                    _ => panic!(
+                        "Cannot merge lattices: Edge ({id}, {nb}) in direction {dir} conflicts with existing edges"
+                    ),
+                }
+            }
+        }
+        if let Some((a, b)) = self.find_layout_conflict() {
+            panic!(
+                "Cannot merge lattices: Nodes {a} and {b} cannot be laid out on a triangular lattice"
+            );
+        }
+    }
+
     /// Return the empty slot with the highest valence and it's neighbors.
     ///
-    /// `visited` and `nb_buf` are temporary buffers used in this function,
-    /// passed in by the caller to avoid allocations.
+    /// `visited` is a temporary buffer used in this function, passed in by the
+    /// caller to avoid allocations. The slots that can't be embedded in the
+    /// grid are filtered out with more temporary buffers, which are allocated
+    /// on every call. Use `empty_slots_with` to reuse those too.
     pub fn empty_slots(
         &self,
         visited: &mut Vec<bool>,
         out: &mut Vec<(u32, Direction, [Neighbor; 6])>,
     ) {
+        let mut bufs = SlotBuffers {
+            visited: std::mem::take(visited),
+            ..SlotBuffers::default()
+        };
+        self.empty_slots_with(&mut bufs, out);
+        *visited = bufs.visited;
+    }
+
+    /// Same as `empty_slots`, but all the temporary buffers are in `bufs`, so
+    /// reusing it across calls avoids allocating at all.
+    pub fn empty_slots_with(
+        &self,
+        bufs: &mut SlotBuffers,
+        out: &mut Vec<(u32, Direction, [Neighbor; 6])>,
+    ) {
+        let visited = &mut bufs.visited;
         visited.clear();
         visited.resize(self.len(), false);
         out.clear();
@@ -276,12 +1154,16 @@ impl Lattice {
                 .neighbor(curid, dir)
                 .expect("Topology is broken if we don't get this");
             dir = dir.opposite();
+            let lap_start = (curid, dir);
             loop {
                 let (next, ndir, nrot) = self
                     .step_loop_cw(curid, dir)
                     .expect("We're on the boundary loop. This should never happen");
                 match nrot {
                     1 => panic!("This implies broken topology. This should never happen"),
+                    // The whole loop is concave when it runs around a hole, so
+                    // stop after one lap.
+                    2 if (next, ndir) == lap_start => break,
                     2 => {
                         curid = next;
                         dir = ndir;
@@ -305,7 +1187,7 @@ impl Lattice {
                     0 | 1 => panic!("This implies broken topology. This should never happen"),
                     2 => {} // Keep going.
                     _ => {
-                        curnb[curndir.opposite().rotate_cw()].put(next);
+                        curnb[curndir.rotate_by(2)].put(next);
                         out.push((curid, curndir, curnb));
                         curnb.fill(Neighbor::default());
                         {
@@ -327,35 +1209,147 @@ impl Lattice {
                 curndir = dir.rotate_cw();
             }
         }
+        self.retain_embeddable_slots(bufs, out);
     }
-}
-
-impl Display for Lattice {
-    /*
-    The hexagonal grids are stored in a coordinate system where the axes are
-    squished together to 60 degrees.
 
-               (0, 1) * ------- * (1, 1)
-                     / \       / \
-                    /   \     /   \
-                   /     \   /     \
-                  /       \ /       \
-          (0, 0) * ------- * ------- * (2, 0)
-                / \     (1, 0)      /
-               /   \     /   \     /
-              /     \   /     \   /
-             /       \ /       \ /
-            * ------- * ------- *
-        (0, -1)     (1, -1)     (2, -1)
+    /// The boundary walk in `empty_slots` only looks at the local topology. It
+    /// doesn't know if the position of a slot is already taken by a node further
+    /// along the boundary, or if the slot is next to nodes across a gap that
+    /// aren't part of the fan it walked. Inserting into such slots breaks the
+    /// lattice, so we lay out each component on the grid and drop them. We also
+    /// drop the slots whose neighbors don't form a single contiguous fan, because
+    /// `insert` can only link the nodes it reaches by orbiting the new node.
+    fn retain_embeddable_slots(
+        &self,
+        bufs: &mut SlotBuffers,
+        slots: &mut Vec<(u32, Direction, [Neighbor; 6])>,
+    ) {
+        let SlotBuffers {
+            visited,
+            stack,
+            nodes,
+            node_pos,
+            positions,
+        } = bufs;
+        visited.fill(false);
+        node_pos.clear();
+        node_pos.resize(self.len(), (0, 0, 0));
+        positions.clear();
+        for start in 0u32..(self.len() as u32) {
+            if visited[start as usize] || !self.contains(start) {
+                continue;
+            }
+            self.walk_component(start, visited, stack, nodes);
+            for &(x, y, node) in nodes.iter() {
+                node_pos[node as usize] = (start, x, y);
+                positions.push(((start, x, y), node));
+            }
+        }
+        positions.sort_unstable();
+        let node_at = |key: (u32, isize, isize)| {
+            positions
+                .binary_search_by_key(&key, |&(pos, _)| pos)
+                .ok()
+                .map(|i| positions[i].1)
+        };
+        slots.retain(|(id, dir, nbs)| {
+            let filled = |d: Direction| nbs[d].is_filled();
+            if Direction::ALL_CCW
+                .into_iter()
+                .filter(|&d| filled(d) && !filled(d.rotate_cw()))
+                .count()
+                != 1
+            {
+                return false;
+            }
+            let (start, x, y) = node_pos[*id as usize];
+            let (dx, dy) = dir.offset();
+            let (x, y) = (x + dx, y + dy);
+            node_at((start, x, y)).is_none()
+                && Direction::ALL_CCW.into_iter().all(|d| {
+                    let (dx, dy) = d.offset();
+                    node_at((start, x + dx, y + dy)) == nbs[d].get()
+                })
+        });
+    }
 
-    This is because a hexagonal grid and a rectangular grid are topologically
-    equivalent. The only difference is that, in the convention of the above diagram,
-    the diagonal in the (-1, 1) direction and the diagonal (1, -1) are considered
-    neighbors of the point in the middle. This means I can store the points in a
-    rectangular grid, and infer the connectivity from the indices.
-     */
+    /// Same as `empty_slots`, but owns its temporary buffers and returns the
+    /// slots as an iterator. Prefer `empty_slots_with` in hot loops, where the
+    /// buffers can be reused.
+    pub fn empty_slots_iter(&self) -> impl Iterator<Item = (u32, Direction, [Neighbor; 6])> {
+        let mut slots = Vec::new();
+        self.empty_slots_with(&mut SlotBuffers::default(), &mut slots);
+        slots.into_iter()
+    }
 
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    /// Directions in which the empty slot described by `nbs`, as reported by
+    /// `empty_slots`, has a neighbor. The directions are in counter clockwise
+    /// order starting from `Direction::RIGHT`.
+    pub fn slot_filled_directions(nbs: &[Neighbor; 6]) -> impl Iterator<Item = Direction> + '_ {
+        Direction::all().filter(|&dir| nbs[dir].is_filled())
+    }
+}
+
+impl Lattice {
+    /// Render the lattice as text, like `Display`, but with the node labels
+    /// centered in cells that are at least `label_width` characters wide. The
+    /// width is rounded up to the next width of the form `4k + 3`, so that the
+    /// diagonal edges land halfway between the labels.
+    pub fn render(&self, label_width: usize) -> String {
+        Self::grid_to_string(self.render_grid(label_width, &[]))
+    }
+
+    /// Render the lattice as text, like `Display`, but with each node in
+    /// `origins` pinned to the given axial coordinates. A component with a
+    /// pinned node is drawn in a frame that also contains the point `(0, 0)`,
+    /// so the same pin gives the same position across lattices, which keeps
+    /// diffs stable. If a component has more than one pinned node, the first
+    /// one wins. Components without a pinned node are laid out as usual.
+    pub fn render_with_origin(&self, origins: &[(u32, isize, isize)]) -> String {
+        Self::grid_to_string(self.render_grid(self.label_width(), origins))
+    }
+
+    fn grid_to_string(grid: Vec<Vec<char>>) -> String {
+        let mut out = String::new();
+        for row in grid {
+            // Keep the blank lines between components empty.
+            if row.iter().any(|&c| c != ' ') {
+                out.extend(row);
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Render the lattice, like `Display`, into a rectangular grid of
+    /// characters, one per cell. This is easier to post-process than a flat
+    /// string.
+    pub fn render_to_grid(&self) -> Vec<Vec<char>> {
+        self.render_grid(self.label_width(), &[])
+    }
+
+    /// Width of the widest node label.
+    fn label_width(&self) -> usize {
+        let max_id = (0..(self.len() as u32))
+            .rev()
+            .find(|&id| self.contains(id))
+            .unwrap_or(0);
+        max_id.to_string().len()
+    }
+
+    fn render_grid(&self, label_width: usize, origins: &[(u32, isize, isize)]) -> Vec<Vec<char>> {
+        let width = (label_width.max(1) + 1).next_multiple_of(4) - 1;
+        // Horizontal distance between adjacent nodes in a row, and the shift
+        // between adjacent rows.
+        let step = width + 1;
+        let half = step / 2;
+        let put = |row: &mut Vec<char>, x: usize, c: char| {
+            if row.len() <= x {
+                row.resize(x + 1, ' ');
+            }
+            row[x] = c;
+        };
+        let mut rows = Vec::new();
         let mut visited = vec![false; self.conn.len()];
         let mut stack = Vec::new();
         let mut component_nodes = Vec::new();
@@ -365,67 +1359,113 @@ impl Display for Lattice {
             {
                 continue;
             }
-            component_nodes.clear();
-            stack.clear();
-            stack.push((start_node as u32, 0isize, 0isize));
-            while let Some((node, x, y)) = stack.pop() {
-                component_nodes.push((x, y, node));
-                for (neighbor, dir) in self.neighbors_with_dirs(node) {
-                    if !std::mem::replace(&mut visited[neighbor as usize], true) {
-                        let (dx, dy) = dir.offset();
-                        stack.push((neighbor, x + dx, y + dy));
-                    }
+            self.walk_component(
+                start_node as u32,
+                &mut visited,
+                &mut stack,
+                &mut component_nodes,
+            );
+            // Move the pinned node, if any, to its position, and make sure the
+            // frame contains the origin.
+            let pin = origins.iter().find_map(|&(pinned, px, py)| {
+                component_nodes
+                    .iter()
+                    .find(|&&(_, _, node)| node == pinned)
+                    .map(|&(x, y, _)| (px - x, py - y))
+            });
+            if let Some((dx, dy)) = pin {
+                for (x, y, _) in &mut component_nodes {
+                    *x += dx;
+                    *y += dy;
                 }
             }
             component_nodes.sort_by(|(ax, ay, _), (cx, cy, _)| {
                 (std::cmp::Reverse(ay), ax + ay).cmp(&(std::cmp::Reverse(cy), cx + cy))
             });
-            let (xmin, _ymin, _xmax, _ymax) = component_nodes.iter().fold(
-                (isize::MAX, isize::MAX, isize::MIN, isize::MIN),
-                |(xmin, ymin, xmax, ymax), &(x, y, _)| {
-                    let x = x * 4 + 2 * y;
-                    let y = y * 2;
-                    (
-                        xmin.min(x - 1),
-                        ymin.min(y - 1),
-                        xmax.max(x + 1),
-                        ymax.max(y + 1),
-                    )
-                },
-            );
-            for row in component_nodes.chunk_by(|(_, ay1, _), (_, ay2, _)| ay1 == ay2) {
-                let mut xoff = 0usize;
-                for &(ix, iy, node) in row {
-                    let has_right = self.neighbor(node, Direction::RIGHT).is_some();
-                    let x = ((ix * 4 + 2 * iy) - xmin) as usize;
-                    for _ in 0..(x - xoff) {
-                        write!(f, " ")?;
+            let column = |x: isize, y: isize| x * step as isize + y * half as isize;
+            let xmin = component_nodes
+                .iter()
+                .map(|&(x, y, _)| column(x, y) - 1)
+                .chain(pin.map(|_| column(0, 0) - 1))
+                .min()
+                .unwrap_or(0);
+            // Rows from top to bottom, including the empty ones between the
+            // nodes and the origin of a pinned component.
+            let ytop = component_nodes.first().map_or(0, |&(_, y, _)| y);
+            let ybottom = component_nodes.last().map_or(0, |&(_, y, _)| y);
+            let (ytop, ybottom) = match pin {
+                Some(_) => (ytop.max(0), ybottom.min(0)),
+                None => (ytop, ybottom),
+            };
+            let mut chunks = component_nodes
+                .chunk_by(|(_, ay1, _), (_, ay2, _)| ay1 == ay2)
+                .peekable();
+            for y in (ybottom..=ytop).rev() {
+                let row = match chunks.next_if(|row| row[0].1 == y) {
+                    Some(row) => row,
+                    None => {
+                        rows.push(Vec::new());
+                        rows.push(Vec::new());
+                        continue;
                     }
-                    write!(f, "{:^3}{}", node, if has_right { "-" } else { " " })?;
-                    xoff = x + 4;
-                }
-                writeln!(f)?;
-                xoff = 0;
+                };
+                let mut labels = Vec::new();
+                let mut diagonals = Vec::new();
                 for &(ix, iy, node) in row {
-                    let has_bottom_left = self.neighbor(node, Direction::BOTTOM_LEFT).is_some();
-                    let has_bottom_right = self.neighbor(node, Direction::BOTTOM_RIGHT).is_some();
-                    let x = ((ix * 4 + 2 * iy) - xmin) as usize;
-                    for _ in 0..(x - xoff) {
-                        write!(f, " ")?;
+                    let x = (column(ix, iy) - xmin) as usize;
+                    for (i, c) in format!("{node:^width$}").chars().enumerate() {
+                        put(&mut labels, x + i, c);
+                    }
+                    if self.neighbor(node, Direction::RIGHT).is_some() {
+                        put(&mut labels, x + width, '-');
+                    }
+                    if self.neighbor(node, Direction::BOTTOM_LEFT).is_some() {
+                        put(&mut diagonals, x + half - 1 - half / 2, '/');
+                    }
+                    if self.neighbor(node, Direction::BOTTOM_RIGHT).is_some() {
+                        put(&mut diagonals, x + half - 1 + half / 2, '\\');
                     }
-                    write!(
-                        f,
-                        "{} {} ",
-                        if has_bottom_left { "/" } else { " " },
-                        if has_bottom_right { "\\" } else { " " }
-                    )?;
-                    xoff = x + 4;
                 }
-                writeln!(f)?;
+                rows.push(labels);
+                rows.push(diagonals);
             }
-            writeln!(f)?;
+            rows.push(Vec::new());
         }
-        Ok(())
+        let ncols = rows.iter().map(Vec::len).max().unwrap_or(0);
+        for row in &mut rows {
+            row.resize(ncols, ' ');
+        }
+        rows
+    }
+}
+
+impl Display for Lattice {
+    /*
+    The hexagonal grids are stored in a coordinate system where the axes are
+    squished together to 60 degrees.
+
+               (0, 1) * ------- * (1, 1)
+                     / \       / \
+                    /   \     /   \
+                   /     \   /     \
+                  /       \ /       \
+          (0, 0) * ------- * ------- * (2, 0)
+                / \     (1, 0)      /
+               /   \     /   \     /
+              /     \   /     \   /
+             /       \ /       \ /
+            * ------- * ------- *
+        (0, -1)     (1, -1)     (2, -1)
+
+    This is because a hexagonal grid and a rectangular grid are topologically
+    equivalent. The only difference is that, in the convention of the above diagram,
+    the diagonal in the (-1, 1) direction and the diagonal (1, -1) are considered
+    neighbors of the point in the middle. This means I can store the points in a
+    rectangular grid, and infer the connectivity from the indices.
+     */
+
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.render(self.label_width()))
     }
 }
 
@@ -433,81 +1473,13 @@ impl Display for Lattice {
 mod test {
     use super::*;
 
-    /// Check lattice for consistency - verifies all lattice invariants
-    fn check_lattice_consistency(lattice: &Lattice) {
-        for node in 0u32..(lattice.len() as u32) {
-            // Skip empty nodes
-            if !lattice.contains(node) {
-                continue;
-            }
-            // Check bidirectional connections
-            for dir in Direction::ALL_CCW {
-                if let Some(neighbor_id) = lattice.neighbor(node, dir) {
-                    // Verify neighbor points back to this node
-                    let back_neighbor = lattice.neighbor(neighbor_id, dir.opposite());
-                    assert_eq!(
-                        back_neighbor,
-                        Some(node),
-                        "Node {} has neighbor {} in direction {:?}, but neighbor {} doesn't point back (has {:?} instead of Some({}))",
-                        node,
-                        neighbor_id,
-                        dir,
-                        neighbor_id,
-                        back_neighbor,
-                        node
-                    );
-                    // Verify neighbor exists in lattice
-                    assert!(
-                        lattice.contains(neighbor_id),
-                        "Node {} has neighbor {} in direction {:?}, but neighbor {} doesn't exist in lattice",
-                        node,
-                        neighbor_id,
-                        dir,
-                        neighbor_id
-                    );
-                }
-            }
-            // Check triangular loops using step_loop functions
-            for (_, dir) in lattice.neighbors_with_dirs(node) {
-                if let Some((last, _)) = (0..3).fold(Some((node, dir)), |current, _| {
-                    if let Some((id, dir)) = current
-                        && let Some((next, ndir, nrot)) = lattice.step_loop_cw(id, dir)
-                        && nrot == 1
-                    {
-                        Some((next, ndir))
-                    } else {
-                        None
-                    }
-                }) {
-                    assert_eq!(last, node);
-                }
-                if let Some((last, _)) = (0..3).fold(Some((node, dir)), |current, _| {
-                    if let Some((id, dir)) = current
-                        && let Some((next, ndir, nrot)) = lattice.step_loop_ccw(id, dir)
-                        && nrot == 1
-                    {
-                        Some((next, ndir))
-                    } else {
-                        None
-                    }
-                }) {
-                    assert_eq!(last, node);
-                }
-            }
-            // Check that no node references itself as a neighbor
-            for neighbor in lattice.neighbors(node) {
-                assert_ne!(neighbor, node, "Node {} has itself as a neighbor", node);
-            }
-        }
-    }
-
     #[test]
     fn test_neighbor_put_get() {
         let test_values = [0, 1, 42, 1000, u32::MAX / 2, u32::MAX - 1];
 
         for &id in &test_values {
             let mut neighbor = Neighbor::default();
-            assert!(!neighbor.get().is_some());
+            assert!(neighbor.get().is_none());
             neighbor.put(id);
             assert_eq!(neighbor.get(), Some(id));
         }
@@ -520,6 +1492,18 @@ mod test {
         assert!(neighbor.get().is_none());
     }
 
+    #[test]
+    fn test_neighbor_try_put() {
+        let mut neighbor = Neighbor::default();
+        assert_eq!(neighbor.try_put(u32::MAX), Err(InvalidNodeId(u32::MAX)));
+        assert!(neighbor.get().is_none());
+        assert_eq!(neighbor.try_put(u32::MAX - 1), Ok(()));
+        assert_eq!(neighbor.get(), Some(u32::MAX - 1));
+        // A failed put leaves the existing value alone.
+        assert!(neighbor.try_put(u32::MAX).is_err());
+        assert_eq!(neighbor.get(), Some(u32::MAX - 1));
+    }
+
     #[test]
     fn test_neighbor_overwrite() {
         let mut neighbor = Neighbor::default();
@@ -529,10 +1513,133 @@ mod test {
         assert_eq!(neighbor.get(), Some(0));
     }
 
+    #[test]
+    fn test_neighbor_count_filled() {
+        let mut nbs = [Neighbor::default(); 6];
+        assert_eq!(Neighbor::count_filled(&nbs), 0);
+        nbs[Direction::LEFT].put(3);
+        assert_eq!(Neighbor::count_filled(&nbs), 1);
+        for (i, nb) in nbs.iter_mut().enumerate() {
+            nb.put(i as u32);
+        }
+        assert_eq!(Neighbor::count_filled(&nbs), 6);
+    }
+
+    #[test]
+    fn test_neighbor_is_filled() {
+        let mut neighbor = Neighbor::default();
+        assert!(neighbor.is_empty());
+        assert!(!neighbor.is_filled());
+        neighbor.put(0);
+        assert!(neighbor.is_filled());
+        assert!(!neighbor.is_empty());
+        neighbor.clear();
+        assert!(neighbor.is_empty());
+    }
+
+    #[test]
+    fn test_direction_rotate_by() {
+        for dir in Direction::all() {
+            assert_eq!(dir.rotate_by(3), dir.opposite());
+            assert_eq!(dir.rotate_by(-3), dir.opposite());
+            assert_eq!(dir.rotate_by(6), dir);
+            assert_eq!(dir.rotate_by(0), dir);
+            assert_eq!(dir.rotate_by(1), dir.rotate_ccw());
+            assert_eq!(dir.rotate_by(-1), dir.rotate_cw());
+            assert_eq!(dir.rotate_by(i8::MIN).rotate_by(i8::MAX), dir.rotate_by(-1));
+        }
+        assert_eq!(Direction::RIGHT.rotate_by(2), Direction::TOP_LEFT);
+        assert_eq!(Direction::RIGHT.rotate_by(-2), Direction::BOTTOM_LEFT);
+    }
+
+    #[test]
+    fn test_direction_angle_degrees() {
+        assert_eq!(Direction::RIGHT.angle_degrees(), 0);
+        assert_eq!(Direction::BOTTOM_RIGHT.angle_degrees(), 300);
+        for dir in Direction::all() {
+            assert_eq!(dir.angle_degrees(), 60 * dir.0 as u16);
+            assert_eq!(
+                (dir.angle_degrees() + 180) % 360,
+                dir.opposite().angle_degrees()
+            );
+            let (x, y) = dir.to_unit_vector();
+            let radians = (dir.angle_degrees() as f64).to_radians();
+            assert!((x - radians.cos()).abs() < 1e-9 && (y - radians.sin()).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_direction_between() {
+        for dir in Direction::all() {
+            let (dx, dy) = dir.offset();
+            assert_eq!(Direction::between((0, 0), (dx, dy)), Some(dir));
+            assert_eq!(Direction::between((3, -2), (3 + dx, -2 + dy)), Some(dir));
+            assert_eq!(Direction::between((dx, dy), (0, 0)), Some(dir.opposite()));
+        }
+        assert_eq!(Direction::between((0, 0), (0, 0)), None);
+        assert_eq!(Direction::between((0, 0), (1, 1)), None);
+        assert_eq!(Direction::between((0, 0), (2, 0)), None);
+    }
+
+    #[test]
+    fn test_direction_from_str_round_trip() {
+        for dir in Direction::ALL_CCW {
+            assert_eq!(dir.to_string().parse::<Direction>(), Ok(dir));
+            assert_eq!(dir.to_string().to_lowercase().parse::<Direction>(), Ok(dir));
+        }
+        assert_eq!("Top_Left".parse::<Direction>(), Ok(Direction::TOP_LEFT));
+    }
+
+    #[test]
+    fn test_direction_from_str_unknown() {
+        assert!("UP".parse::<Direction>().is_err());
+        assert!("".parse::<Direction>().is_err());
+        assert!("RIGHT ".parse::<Direction>().is_err());
+    }
+
+    #[test]
+    fn test_direction_all() {
+        let dirs: Vec<_> = Direction::all().collect();
+        assert_eq!(dirs.len(), 6);
+        for (i, a) in dirs.iter().enumerate() {
+            for b in &dirs[(i + 1)..] {
+                assert_ne!(a, b);
+            }
+        }
+        for dir in Direction::all() {
+            assert!(dirs.contains(&dir.opposite()));
+            assert_ne!(dir, dir.opposite());
+            assert_eq!(dir, dir.opposite().opposite());
+        }
+    }
+
+    #[test]
+    fn test_direction_try_from_u8() {
+        for (i, dir) in Direction::ALL_CCW.into_iter().enumerate() {
+            assert_eq!(Direction::try_from(i as u8), Ok(dir));
+        }
+        assert_eq!(Direction::try_from(6), Err(InvalidDirection(6)));
+        assert_eq!(Direction::try_from(255), Err(InvalidDirection(255)));
+    }
+
+    #[test]
+    fn test_direction_unit_vector() {
+        let (x, y) = Direction::RIGHT.to_unit_vector();
+        assert!((x - 1.0).abs() < 1e-12 && y.abs() < 1e-12);
+        let (x, y) = Direction::TOP_RIGHT.to_unit_vector();
+        assert!((x - 0.5).abs() < 1e-12 && (y - 3f64.sqrt() / 2.0).abs() < 1e-12);
+        for dir in Direction::all() {
+            let (x, y) = dir.to_unit_vector();
+            assert!((x.hypot(y) - 1.0).abs() < 1e-12);
+            let (ox, oy) = dir.opposite().to_unit_vector();
+            assert!((x + ox).abs() < 1e-12 && (y + oy).abs() < 1e-12);
+        }
+    }
+
     #[test]
     fn test_print_empty_lattice() {
         let lattice = Lattice::new(5);
-        check_lattice_consistency(&lattice);
+        lattice.validate();
         let output = format!("{}", lattice);
         assert_eq!(output, "");
     }
@@ -542,7 +1649,7 @@ mod test {
         let mut lattice = Lattice::new(1);
         // Insert node 0 to itself to create a self-loop
         lattice.insert(0, Direction::RIGHT, 0); // Has no effect.
-        check_lattice_consistency(&lattice);
+        lattice.validate();
         assert!(format!("{}", lattice).is_empty());
     }
 
@@ -550,7 +1657,7 @@ mod test {
     fn test_print_two_node_connection() {
         let mut lattice = Lattice::new(2);
         lattice.insert(0, Direction::RIGHT, 1);
-        check_lattice_consistency(&lattice);
+        lattice.validate();
         assert_eq!(format!("{}", lattice).trim(), "0 - 1");
     }
 
@@ -561,7 +1668,7 @@ mod test {
         lattice.insert(0, Direction::RIGHT, 1);
         // Then insert the third node to form a triangle
         lattice.insert(0, Direction::TOP_RIGHT, 2);
-        check_lattice_consistency(&lattice);
+        lattice.validate();
         let output = format!("{}", lattice);
         assert!(output.contains(" 0 "));
         assert!(output.contains(" 1 "));
@@ -577,7 +1684,7 @@ mod test {
         lattice.insert(0, Direction::RIGHT, 1);
         lattice.insert(1, Direction::RIGHT, 2);
         lattice.insert(2, Direction::RIGHT, 3);
-        check_lattice_consistency(&lattice);
+        lattice.validate();
         let output = format!("{}", lattice);
         for i in 0..4 {
             assert!(output.contains(&format!(" {} ", i)));
@@ -597,7 +1704,7 @@ mod test {
         // Create second linear component
         lattice.insert(3, Direction::RIGHT, 4);
         lattice.insert(4, Direction::RIGHT, 5);
-        check_lattice_consistency(&lattice);
+        lattice.validate();
         let output = format!("{}", lattice);
         // All nodes should be present
         for i in 0..6 {
@@ -616,7 +1723,7 @@ mod test {
         lattice.insert(0, Direction::TOP_RIGHT, 2);
         // Create a separate small component
         lattice.insert(3, Direction::RIGHT, 4);
-        check_lattice_consistency(&lattice);
+        lattice.validate();
         let output = format!("{}", lattice);
         // All nodes should be present
         assert!(output.contains(" 0 "));
@@ -639,7 +1746,7 @@ mod test {
         lattice.insert(0, Direction::LEFT, 4);
         lattice.insert(0, Direction::BOTTOM_LEFT, 5);
         lattice.insert(0, Direction::BOTTOM_RIGHT, 6);
-        check_lattice_consistency(&lattice);
+        lattice.validate();
         let output = format!("{}", lattice);
         // All nodes in star pattern should be present
         for i in 0..7 {
@@ -651,6 +1758,1022 @@ mod test {
         assert!(output.contains("\\")); // diagonal connections
     }
 
+    #[test]
+    fn test_print_wide_labels() {
+        let mut lattice = Lattice::new(1300);
+        lattice.insert(0, Direction::RIGHT, 1234);
+        lattice.insert(0, Direction::TOP_RIGHT, 5);
+        lattice.insert(0, Direction::BOTTOM_RIGHT, 42);
+        let output = format!("{}", lattice);
+        assert!(output.contains("0   - 1234"));
+        let lines: Vec<_> = output.lines().collect();
+        assert_eq!(lines[0].trim_end(), "        5");
+        assert_eq!(lines[1].trim_end(), "      /   \\");
+        assert_eq!(lines[2].trim_end(), "    0   - 1234");
+        assert_eq!(lines[3].trim_end(), "      \\   /");
+        assert_eq!(lines[4].trim_end(), "       42");
+        // Widths are rounded up, so the diagonals still line up.
+        assert_eq!(lattice.render(5), output);
+    }
+
+    #[test]
+    fn test_render_default_width() {
+        let mut lattice = Lattice::new(3);
+        lattice.insert(0, Direction::RIGHT, 1);
+        lattice.insert(0, Direction::TOP_RIGHT, 2);
+        assert_eq!(lattice.render(1), format!("{}", lattice));
+        assert_eq!(lattice.render(3), format!("{}", lattice));
+        assert!(lattice.render(7).contains("   0   -   1   "));
+    }
+
+    #[test]
+    fn test_render_with_origin() {
+        let mut lattice = Lattice::new(5);
+        lattice.insert(0, Direction::RIGHT, 1);
+        lattice.insert(0, Direction::TOP_RIGHT, 2);
+        lattice.insert(3, Direction::RIGHT, 4);
+        assert_eq!(lattice.render_with_origin(&[]), format!("{}", lattice));
+        // 0 is already at the origin of its component.
+        assert_eq!(
+            lattice.render_with_origin(&[(0, 0, 0)]),
+            format!("{}", lattice)
+        );
+        // Each step right moves a node 4 columns, and each step up moves it 2
+        // columns right and 2 lines up.
+        let pinned = lattice.render_with_origin(&[(0, 5, 5)]);
+        // Rows are padded to the widest row, so ignore trailing spaces.
+        let plain: Vec<_> = lattice
+            .to_string()
+            .lines()
+            .map(|line| line.trim_end().to_string())
+            .collect();
+        let shift = " ".repeat(5 * 4 + 5 * 2);
+        let mut expected: Vec<String> = plain[..4]
+            .iter()
+            .map(|line| match line.is_empty() {
+                true => String::new(),
+                false => format!("{shift}{line}"),
+            })
+            .collect();
+        // Rows 4 down to 0 are empty.
+        expected.extend(std::iter::repeat_n(String::new(), 10));
+        // The other component is not pinned, so it doesn't move.
+        expected.extend(plain[4..].iter().cloned());
+        assert_eq!(
+            pinned.lines().map(str::trim_end).collect::<Vec<_>>(),
+            expected
+        );
+        // Pinning a node of the other component.
+        let pinned = lattice.render_with_origin(&[(4, 1, 0)]);
+        assert!(pinned.contains(&format!("\n{}3 - 4", " ".repeat(2))));
+    }
+
+    #[test]
+    fn test_render_to_grid_triangle() {
+        let mut lattice = Lattice::new(3);
+        lattice.insert(0, Direction::RIGHT, 1);
+        lattice.insert(0, Direction::TOP_RIGHT, 2);
+        let grid = lattice.render_to_grid();
+        assert_eq!(grid.len(), 5);
+        assert!(grid.iter().all(|row| row.len() == 8));
+        // 2 sits above and halfway between 0 and 1.
+        assert_eq!(grid[0][4], '2');
+        assert_eq!(grid[1][3], '/');
+        assert_eq!(grid[1][5], '\\');
+        assert_eq!(grid[2][2], '0');
+        assert_eq!(grid[2][4], '-');
+        assert_eq!(grid[2][6], '1');
+        let text: String = grid
+            .iter()
+            .map(|row| row.iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(text.trim(), format!("{}", lattice).trim());
+    }
+
+    #[test]
+    fn test_interior_and_boundary_nodes() {
+        let mut lattice = Lattice::new(8);
+        // Full hexagon around node 0.
+        lattice.insert(0, Direction::RIGHT, 1);
+        lattice.insert(0, Direction::TOP_RIGHT, 2);
+        lattice.insert(0, Direction::TOP_LEFT, 3);
+        lattice.insert(0, Direction::LEFT, 4);
+        lattice.insert(0, Direction::BOTTOM_LEFT, 5);
+        lattice.insert(0, Direction::BOTTOM_RIGHT, 6);
+        lattice.validate();
+        assert!(lattice.is_interior(0));
+        for i in 1..7 {
+            assert!(!lattice.is_interior(i));
+        }
+        // Node 7 is not in the lattice, so it is neither interior nor boundary.
+        assert!(!lattice.is_interior(7));
+        assert_eq!(lattice.interior_nodes().collect::<Vec<_>>(), vec![0]);
+        assert_eq!(
+            lattice.boundary_nodes().collect::<Vec<_>>(),
+            vec![1, 2, 3, 4, 5, 6]
+        );
+    }
+
+    #[test]
+    fn test_merge_disjoint_triangles() {
+        let mut lattice = Lattice::new(6);
+        lattice.insert(0, Direction::RIGHT, 1);
+        lattice.insert(0, Direction::TOP_RIGHT, 2);
+        let mut other = Lattice::new(6);
+        other.insert(3, Direction::RIGHT, 4);
+        other.insert(3, Direction::TOP_RIGHT, 5);
+        let mut expected: Vec<_> = lattice.edges().chain(other.edges()).collect();
+        expected.sort();
+        lattice.merge(&other);
+        lattice.validate();
+        let mut edges: Vec<_> = lattice.edges().collect();
+        edges.sort();
+        assert_eq!(edges, expected);
+        assert_eq!(edges.len(), 6);
+    }
+
+    #[test]
+    fn test_merge_overlapping_triangles() {
+        // Two triangles sharing the edge (0, 1), forming a rhombus.
+        let mut lattice = Lattice::new(4);
+        lattice.insert(0, Direction::RIGHT, 1);
+        lattice.insert(0, Direction::TOP_RIGHT, 2);
+        let mut other = Lattice::new(4);
+        other.insert(0, Direction::RIGHT, 1);
+        other.insert(0, Direction::BOTTOM_RIGHT, 3);
+        lattice.merge(&other);
+        lattice.validate();
+        assert_eq!(lattice.edges().count(), 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot merge lattices")]
+    fn test_merge_conflicting_slots() {
+        let mut lattice = Lattice::new(3);
+        lattice.insert(0, Direction::RIGHT, 1);
+        let mut other = Lattice::new(3);
+        other.insert(0, Direction::RIGHT, 2);
+        lattice.merge(&other);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot be laid out")]
+    fn test_merge_unrealizable_layout() {
+        // Node 2 is to the right of 1 in one lattice, and to the top right of
+        // 0 in the other. It cannot be in both places at once.
+        let mut lattice = Lattice::new(3);
+        lattice.insert(0, Direction::RIGHT, 1);
+        lattice.insert(1, Direction::RIGHT, 2);
+        let mut other = Lattice::new(3);
+        other.insert(0, Direction::TOP_RIGHT, 2);
+        lattice.merge(&other);
+    }
+
+    #[test]
+    fn test_remove_present_node() {
+        let mut lattice = Lattice::new(4);
+        lattice.insert(0, Direction::RIGHT, 1);
+        lattice.insert(0, Direction::TOP_RIGHT, 2);
+        lattice.insert(1, Direction::TOP_RIGHT, 3);
+        assert!(lattice.remove(2));
+        lattice.validate();
+        assert!(!lattice.contains(2));
+        assert_eq!(lattice.edges().collect::<Vec<_>>(), vec![(0, 1), (1, 3)]);
+    }
+
+    #[test]
+    fn test_refill_hole() {
+        // A full ring around 0, and then take 0 out to leave a hole.
+        let mut lattice = Lattice::new(7);
+        for (dir, id) in Direction::ALL_CCW.into_iter().zip(1u32..) {
+            lattice.insert(0, dir, id);
+        }
+        assert!(lattice.remove(0));
+        lattice.validate();
+        // The hole is walked without getting stuck, and isn't offered as a slot.
+        let slots: Vec<_> = lattice.empty_slots_iter().collect();
+        assert!(!slots.is_empty());
+        assert!(
+            slots
+                .iter()
+                .all(|&(id, dir, _)| lattice.neighbor(id, dir).is_none())
+        );
+        // Filling the hole links the new node to the whole ring.
+        lattice.insert(1, Direction::LEFT, 0);
+        lattice.validate();
+        assert_eq!(lattice.degree(0), 6);
+        assert_eq!(lattice.edge_count(), 12);
+    }
+
+    #[test]
+    fn test_truncate() {
+        let mut lattice = Lattice::new(6);
+        lattice.insert_path(
+            0,
+            &[
+                (Direction::RIGHT, 1),
+                (Direction::RIGHT, 2),
+                (Direction::RIGHT, 3),
+                (Direction::RIGHT, 4),
+            ],
+        );
+        lattice.truncate(3);
+        assert_eq!(lattice.len(), 3);
+        lattice.validate();
+        assert_eq!(lattice.edges().collect::<Vec<_>>(), vec![(0, 1), (1, 2)]);
+        assert_eq!(lattice.degree(2), 1);
+        // Growing is not possible.
+        lattice.truncate(10);
+        assert_eq!(lattice.len(), 3);
+        lattice.truncate(0);
+        assert!(lattice.is_empty());
+    }
+
+    #[test]
+    fn test_remove_absent_node() {
+        let mut lattice = Lattice::new(4);
+        lattice.insert(0, Direction::RIGHT, 1);
+        assert!(!lattice.remove(3));
+        lattice.validate();
+        assert_eq!(lattice.edges().collect::<Vec<_>>(), vec![(0, 1)]);
+        // Removing the same node twice is a no-op the second time.
+        assert!(lattice.remove(1));
+        assert!(!lattice.remove(1));
+        lattice.validate();
+        assert_eq!(lattice.edges().count(), 0);
+    }
+
+    #[test]
+    fn test_try_insert_into_empty_slot() {
+        let mut lattice = Lattice::new(3);
+        assert_eq!(lattice.try_insert(0, Direction::RIGHT, 1), Ok(()));
+        assert_eq!(lattice.try_insert(0, Direction::TOP_RIGHT, 2), Ok(()));
+        lattice.validate();
+        assert_eq!(lattice.edges().count(), 3);
+        // Inserting an existing link again is fine.
+        assert_eq!(lattice.try_insert(1, Direction::LEFT, 0), Ok(()));
+        assert_eq!(lattice.edges().count(), 3);
+    }
+
+    #[test]
+    fn test_try_insert_source_occupied() {
+        let mut lattice = Lattice::new(3);
+        lattice.insert(0, Direction::RIGHT, 1);
+        assert_eq!(
+            lattice.try_insert(0, Direction::RIGHT, 2),
+            Err(InsertError::SourceOccupied(1))
+        );
+        // Nothing was displaced.
+        lattice.validate();
+        assert_eq!(lattice.edges().collect::<Vec<_>>(), vec![(0, 1)]);
+        assert!(!lattice.contains(2));
+    }
+
+    #[test]
+    fn test_try_insert_destination_occupied() {
+        let mut lattice = Lattice::new(3);
+        lattice.insert(0, Direction::RIGHT, 1);
+        // Node 0 already has 1 on its right, so 2 can't put 0 on its right.
+        assert_eq!(
+            lattice.try_insert(2, Direction::LEFT, 0),
+            Err(InsertError::DestinationOccupied(1))
+        );
+        lattice.validate();
+        assert_eq!(lattice.edges().collect::<Vec<_>>(), vec![(0, 1)]);
+    }
+
+    #[test]
+    fn test_try_insert_self() {
+        let mut lattice = Lattice::new(3);
+        lattice.insert(0, Direction::RIGHT, 1);
+        assert_eq!(
+            lattice.try_insert(1, Direction::RIGHT, 1),
+            Err(InsertError::SelfLoop(1))
+        );
+        assert_eq!(
+            lattice.try_insert(2, Direction::RIGHT, 2),
+            Err(InsertError::SelfLoop(2))
+        );
+        // The lenient `insert` still ignores it.
+        lattice.insert(2, Direction::RIGHT, 2);
+        lattice.validate();
+        assert_eq!(lattice.edges().collect::<Vec<_>>(), vec![(0, 1)]);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "Node 2 has neighbor 1 in more than one slot")]
+    fn test_insert_catches_broken_topology() {
+        let mut lattice = Lattice::new(3);
+        lattice.insert(0, Direction::RIGHT, 1);
+        lattice.insert(0, Direction::TOP_RIGHT, 2);
+        // 2 is already above 1, so putting it to the right of 1 as well breaks
+        // the lattice.
+        lattice.insert(1, Direction::RIGHT, 2);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "Node 2 has neighbor 1 in more than one slot")]
+    fn test_insert_validated_catches_broken_topology() {
+        let mut lattice = Lattice::new(3);
+        lattice.insert_validated(0, Direction::RIGHT, 1);
+        lattice.insert_validated(0, Direction::TOP_RIGHT, 2);
+        // Caught here, without calling `validate`.
+        lattice.insert_validated(1, Direction::RIGHT, 2);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn test_insert_validated_agrees_with_validate_on_broken_insert() {
+        use std::panic::{AssertUnwindSafe, catch_unwind};
+        let mut lattice = Lattice::new(3);
+        lattice.insert_validated(0, Direction::RIGHT, 1);
+        lattice.insert_validated(0, Direction::TOP_RIGHT, 2);
+        let mut broken = lattice.clone();
+        broken.insert_unchecked(1, Direction::RIGHT, 2);
+        assert!(catch_unwind(|| broken.validate()).is_err());
+        assert!(
+            catch_unwind(AssertUnwindSafe(|| {
+                lattice.insert_validated(1, Direction::RIGHT, 2)
+            }))
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_insert_validated_matches_insert() {
+        let mut validated = Lattice::new(19);
+        let mut plain = Lattice::new(19);
+        validated.insert_validated(0, Direction::RIGHT, 1);
+        plain.insert(0, Direction::RIGHT, 1);
+        let mut bufs = SlotBuffers::default();
+        let mut slots = Vec::new();
+        for id in 2u32..19 {
+            validated.empty_slots_with(&mut bufs, &mut slots);
+            let (from, dir, _) = slots[(id as usize * 7) % slots.len()];
+            validated.insert_validated(from, dir, id);
+            plain.insert(from, dir, id);
+            validated.validate();
+            assert_eq!(
+                validated.edges().collect::<Vec<_>>(),
+                plain.edges().collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn test_can_insert() {
+        let mut lattice = Lattice::new(3);
+        assert!(lattice.can_insert(0, Direction::RIGHT, 1));
+        lattice.insert(0, Direction::RIGHT, 1);
+        // Both the source and the mirrored destination slot must be empty.
+        assert!(!lattice.can_insert(0, Direction::RIGHT, 2));
+        assert!(!lattice.can_insert(2, Direction::LEFT, 0));
+        assert!(lattice.can_insert(0, Direction::TOP_RIGHT, 2));
+        assert!(!lattice.can_insert(2, Direction::RIGHT, 2));
+        // Nothing changes.
+        lattice.validate();
+        assert_eq!(lattice.edges().collect::<Vec<_>>(), vec![(0, 1)]);
+    }
+
+    #[test]
+    fn test_components_single_triangle() {
+        let mut lattice = Lattice::new(4);
+        lattice.insert(1, Direction::RIGHT, 2);
+        lattice.insert(1, Direction::TOP_RIGHT, 3);
+        assert_eq!(lattice.components(), vec![vec![1, 2, 3]]);
+    }
+
+    #[test]
+    fn test_components_disjoint_edges() {
+        let mut lattice = Lattice::new(4);
+        lattice.insert(0, Direction::RIGHT, 3);
+        lattice.insert(2, Direction::TOP_LEFT, 1);
+        assert_eq!(lattice.components(), vec![vec![0, 3], vec![1, 2]]);
+    }
+
+    #[test]
+    fn test_components_empty_lattice() {
+        assert!(Lattice::new(5).components().is_empty());
+    }
+
+    #[test]
+    fn test_component_of_disjoint_triangles() {
+        let mut lattice = Lattice::new(6);
+        lattice.insert(0, Direction::RIGHT, 1);
+        lattice.insert(0, Direction::TOP_RIGHT, 2);
+        lattice.insert(3, Direction::RIGHT, 4);
+        lattice.insert(3, Direction::TOP_RIGHT, 5);
+        let first = lattice.component(0);
+        first.validate();
+        assert_eq!(first.len(), 6);
+        let mut edges: Vec<_> = first.edges().collect();
+        edges.sort();
+        assert_eq!(edges, vec![(0, 1), (0, 2), (1, 2)]);
+        for i in 3..6 {
+            assert!(!first.contains(i));
+        }
+        let second = lattice.component(4);
+        second.validate();
+        let mut edges: Vec<_> = second.edges().collect();
+        edges.sort();
+        assert_eq!(edges, vec![(3, 4), (3, 5), (4, 5)]);
+    }
+
+    #[test]
+    fn test_component_of_missing_node() {
+        let mut lattice = Lattice::new(4);
+        lattice.insert(0, Direction::RIGHT, 1);
+        assert_eq!(lattice.component(3).edges().count(), 0);
+    }
+
+    #[test]
+    fn test_node_at_chain() {
+        let mut lattice = Lattice::new(5);
+        lattice.insert_path(
+            0,
+            &[
+                (Direction::RIGHT, 1),
+                (Direction::RIGHT, 2),
+                (Direction::RIGHT, 3),
+            ],
+        );
+        for k in 0..4 {
+            assert_eq!(lattice.node_at(0, k, 0), Some(k as u32));
+            assert_eq!(lattice.node_at(3, k - 3, 0), Some(k as u32));
+        }
+        assert_eq!(lattice.node_at(0, 4, 0), None);
+        assert_eq!(lattice.node_at(0, 1, 1), None);
+        assert_eq!(lattice.node_at(4, 0, 0), None);
+    }
+
+    #[test]
+    fn test_with_edges_star() {
+        let mut expected = Lattice::new(7);
+        expected.insert(0, Direction::RIGHT, 1);
+        expected.insert(0, Direction::TOP_RIGHT, 2);
+        expected.insert(0, Direction::TOP_LEFT, 3);
+        expected.insert(0, Direction::LEFT, 4);
+        expected.insert(0, Direction::BOTTOM_LEFT, 5);
+        expected.insert(0, Direction::BOTTOM_RIGHT, 6);
+        let lattice = Lattice::with_edges(
+            7,
+            &[
+                (0, Direction::RIGHT, 1),
+                (0, Direction::TOP_RIGHT, 2),
+                (0, Direction::TOP_LEFT, 3),
+                (0, Direction::LEFT, 4),
+                (0, Direction::BOTTOM_LEFT, 5),
+                (0, Direction::BOTTOM_RIGHT, 6),
+            ],
+        );
+        assert_eq!(
+            lattice.edges().collect::<Vec<_>>(),
+            expected.edges().collect::<Vec<_>>()
+        );
+        assert_eq!(format!("{lattice}"), format!("{expected}"));
+    }
+
+    #[test]
+    fn test_insert_path() {
+        let mut expected = Lattice::new(4);
+        expected.insert(0, Direction::RIGHT, 1);
+        expected.insert(1, Direction::RIGHT, 2);
+        expected.insert(2, Direction::RIGHT, 3);
+        let mut lattice = Lattice::new(4);
+        lattice.insert_path(
+            0,
+            &[
+                (Direction::RIGHT, 1),
+                (Direction::RIGHT, 2),
+                (Direction::RIGHT, 3),
+            ],
+        );
+        lattice.validate();
+        assert_eq!(
+            lattice.edges().collect::<Vec<_>>(),
+            expected.edges().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_is_triangulated() {
+        let mut lattice = Lattice::new(8);
+        assert!(lattice.is_triangulated());
+        lattice.insert(0, Direction::RIGHT, 1);
+        assert!(!lattice.is_triangulated());
+        lattice.insert(0, Direction::TOP_RIGHT, 2);
+        assert!(lattice.is_triangulated());
+        for (i, dir) in Direction::all().enumerate().skip(2) {
+            lattice.insert(0, dir, i as u32 + 1);
+        }
+        assert!(lattice.is_triangulated());
+        lattice.insert(1, Direction::RIGHT, 7);
+        assert!(!lattice.is_triangulated());
+    }
+
+    #[test]
+    fn test_neighbors_with_coords() {
+        let mut lattice = Lattice::new(3);
+        lattice.insert(0, Direction::RIGHT, 1);
+        lattice.insert(0, Direction::TOP_RIGHT, 2);
+        let mut nbs: Vec<_> = lattice.neighbors_with_coords(0, (0, 0)).collect();
+        nbs.sort();
+        assert_eq!(nbs, vec![(1, 1, 0), (2, 0, 1)]);
+        let mut nbs: Vec<_> = lattice.neighbors_with_coords(1, (5, -2)).collect();
+        nbs.sort();
+        assert_eq!(nbs, vec![(0, 4, -2), (2, 4, -1)]);
+    }
+
+    #[test]
+    fn test_faces() {
+        let mut lattice = Lattice::new(8);
+        assert_eq!(lattice.faces().count(), 0);
+        for (i, dir) in Direction::all().enumerate() {
+            lattice.insert(0, dir, i as u32 + 1);
+        }
+        let faces: Vec<_> = lattice.faces().collect();
+        assert_eq!(faces.len(), 6);
+        assert!(faces.contains(&[0, 1, 2]));
+        assert!(faces.contains(&[0, 6, 1]));
+        lattice.insert(1, Direction::RIGHT, 7);
+        assert_eq!(lattice.faces().count(), 6);
+    }
+
+    #[test]
+    fn test_from_graph_triangle() {
+        let mut graph = Graph::new_empty(4);
+        graph.add_edge(0, 1);
+        graph.add_edge(1, 2);
+        graph.add_edge(2, 0);
+        let lattice = Lattice::from_graph(&graph).unwrap();
+        lattice.validate();
+        assert_eq!(
+            lattice.edges().collect::<Vec<_>>(),
+            vec![(0, 1), (0, 2), (1, 2)]
+        );
+        assert_eq!(lattice.faces().count(), 1);
+        assert!(!lattice.contains(3));
+    }
+
+    #[test]
+    fn test_from_graph_round_trip() {
+        let mut lattice = Lattice::new(9);
+        for (dir, id) in Direction::all().zip(1u32..) {
+            lattice.insert(0, dir, id);
+        }
+        lattice.insert(1, Direction::RIGHT, 7);
+        lattice.insert(7, Direction::BOTTOM_RIGHT, 8);
+        let graph = lattice.to_graph();
+        let realized = Lattice::from_graph(&graph).unwrap();
+        realized.validate();
+        assert!(realized.to_graph() == graph);
+    }
+
+    #[test]
+    fn test_from_graph_not_a_lattice() {
+        assert_eq!(
+            Lattice::from_graph(&Graph::new_complete(4)).err(),
+            Some(NotALattice)
+        );
+        // Two separate edges are not a single lattice.
+        let mut graph = Graph::new_empty(4);
+        graph.add_edge(0, 1);
+        graph.add_edge(2, 3);
+        assert_eq!(Lattice::from_graph(&graph).err(), Some(NotALattice));
+        // A 4-cycle can't be laid out without a diagonal.
+        let mut graph = Graph::new_empty(4);
+        for (a, b) in [(0, 1), (1, 2), (2, 3), (3, 0)] {
+            graph.add_edge(a, b);
+        }
+        assert_eq!(Lattice::from_graph(&graph).err(), Some(NotALattice));
+        assert_eq!(
+            Lattice::from_graph(&Graph::new_empty(3))
+                .unwrap()
+                .edge_count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_walk() {
+        let mut triangle = Lattice::new(4);
+        triangle.insert(0, Direction::RIGHT, 1);
+        triangle.insert(0, Direction::TOP_RIGHT, 2);
+        // One clockwise turn at 1 faces the third corner of the triangle.
+        assert_eq!(
+            triangle.walk_cw(0, Direction::RIGHT),
+            Some((1, Direction::TOP_LEFT, 1))
+        );
+        assert_eq!(triangle.neighbor_in(1, Direction::TOP_LEFT), Some(2));
+        assert_eq!(
+            triangle.walk_ccw(0, Direction::TOP_RIGHT),
+            Some((2, Direction::BOTTOM_RIGHT, 1))
+        );
+        // Three steps walk around the triangle and back to the start.
+        let (id, dir) = (0..3).fold((0, Direction::RIGHT), |(id, dir), _| {
+            let (next, ndir, rotations) = triangle.walk_cw(id, dir).unwrap();
+            assert_eq!(rotations, 1);
+            (next, ndir)
+        });
+        assert_eq!((id, dir), (0, Direction::RIGHT));
+        // The long way around the outside of the triangle.
+        assert_eq!(
+            triangle.walk_ccw(0, Direction::RIGHT),
+            Some((1, Direction::TOP_LEFT, 5))
+        );
+        assert_eq!(triangle.walk_cw(0, Direction::LEFT), None);
+        // A dead end turns all the way back.
+        triangle.insert(1, Direction::RIGHT, 3);
+        assert_eq!(
+            triangle.walk_cw(1, Direction::RIGHT),
+            Some((3, Direction::LEFT, 6))
+        );
+    }
+
+    #[test]
+    fn test_neighbor_in() {
+        let mut lattice = Lattice::new(3);
+        lattice.insert(0, Direction::RIGHT, 1);
+        lattice.insert(0, Direction::TOP_RIGHT, 2);
+        let expected = [
+            (0, [Some(1), Some(2), None, None, None, None]),
+            (1, [None, None, Some(2), Some(0), None, None]),
+            (2, [None, None, None, None, Some(0), Some(1)]),
+        ];
+        for (id, nbs) in expected {
+            for (dir, nb) in Direction::all().zip(nbs) {
+                assert_eq!(lattice.neighbor_in(id, dir), nb);
+            }
+        }
+    }
+
+    #[test]
+    fn test_iter_nodes() {
+        let mut lattice = Lattice::new(12);
+        assert_eq!(lattice.iter_nodes().count(), 0);
+        lattice.insert(0, Direction::RIGHT, 5);
+        lattice.insert(5, Direction::TOP_RIGHT, 9);
+        assert_eq!(lattice.iter_nodes().collect::<Vec<_>>(), vec![0, 5, 9]);
+    }
+
+    #[test]
+    fn test_contains_edge() {
+        let mut triangle = Lattice::new(3);
+        triangle.insert(0, Direction::RIGHT, 1);
+        triangle.insert(0, Direction::TOP_RIGHT, 2);
+        for (a, b) in [(0, 1), (1, 2), (2, 0)] {
+            assert!(triangle.contains_edge(a, b));
+            assert!(triangle.contains_edge(b, a));
+        }
+        let mut chain = Lattice::new(3);
+        chain.insert_path(0, &[(Direction::RIGHT, 1), (Direction::RIGHT, 2)]);
+        assert!(chain.contains_edge(1, 2));
+        assert!(!chain.contains_edge(0, 2));
+        assert!(!chain.contains_edge(0, 0));
+    }
+
+    #[test]
+    fn test_common_neighbors() {
+        // Rhombus made of the triangles (0, 1, 2) and (0, 3, 1).
+        let mut rhombus = Lattice::new(4);
+        rhombus.insert(0, Direction::RIGHT, 1);
+        rhombus.insert(0, Direction::TOP_RIGHT, 2);
+        rhombus.insert(0, Direction::BOTTOM_RIGHT, 3);
+        let mut common: Vec<_> = rhombus.common_neighbors(0, 1).collect();
+        common.sort();
+        assert_eq!(common, vec![2, 3]);
+        assert_eq!(rhombus.common_neighbors(2, 0).collect::<Vec<_>>(), vec![1]);
+        assert_eq!(
+            rhombus.common_neighbors(2, 3).collect::<Vec<_>>(),
+            vec![0, 1]
+        );
+    }
+
+    #[test]
+    fn test_euler_characteristic() {
+        let mut lattice = Lattice::new(10);
+        lattice.insert(0, Direction::RIGHT, 1);
+        lattice.insert(0, Direction::TOP_RIGHT, 2);
+        assert_eq!(lattice.euler_characteristic(0), 1);
+        for (dir, id) in Direction::all().zip(1u32..) {
+            lattice.insert(0, dir, id);
+        }
+        lattice.insert(8, Direction::RIGHT, 9);
+        assert_eq!(lattice.euler_characteristic(3), 1);
+        assert_eq!(lattice.euler_characteristic(8), 1);
+        assert_eq!(lattice.euler_characteristic(7), 0);
+        // A ring around a hole is not a disk.
+        lattice.remove(0);
+        assert_eq!(lattice.euler_characteristic(1), 0);
+    }
+
+    #[test]
+    fn test_triangle_count() {
+        let mut lattice = Lattice::new(8);
+        lattice.insert(0, Direction::RIGHT, 1);
+        assert_eq!(lattice.triangle_count(), 0);
+        lattice.insert(0, Direction::TOP_RIGHT, 2);
+        assert_eq!(lattice.triangle_count(), 1);
+        for (dir, id) in Direction::all().zip(1u32..) {
+            lattice.insert(0, dir, id);
+        }
+        assert_eq!(lattice.triangle_count(), 6);
+        for n in [7, 12, 20] {
+            for lattice in crate::greedy::solve_greedy::<Graph>(n) {
+                assert_eq!(lattice.triangle_count(), lattice.faces().count());
+            }
+        }
+    }
+
+    #[test]
+    fn test_face_adjacency_rhombus() {
+        let mut lattice = Lattice::new(4);
+        lattice.insert(0, Direction::RIGHT, 1);
+        lattice.insert(0, Direction::TOP_RIGHT, 2);
+        lattice.insert(1, Direction::TOP_RIGHT, 3);
+        let adjacency = lattice.face_adjacency();
+        assert_eq!(adjacency, vec![([0, 1, 2], vec![1]), ([1, 3, 2], vec![0])]);
+    }
+
+    #[test]
+    fn test_compacted() {
+        let mut lattice = Lattice::new(12);
+        lattice.insert(0, Direction::RIGHT, 5);
+        lattice.insert(0, Direction::TOP_RIGHT, 9);
+        let (compact, old_ids) = lattice.compacted();
+        assert_eq!(compact.len(), 3);
+        assert_eq!(old_ids, vec![0, 5, 9]);
+        compact.validate();
+        assert_eq!(
+            compact.edges().collect::<Vec<_>>(),
+            vec![(0, 1), (0, 2), (1, 2)]
+        );
+        assert_eq!(compact.neighbor(0, Direction::RIGHT), Some(1));
+        assert_eq!(compact.neighbor(0, Direction::TOP_RIGHT), Some(2));
+        let (empty, old_ids) = Lattice::new(4).compacted();
+        assert!(empty.is_empty());
+        assert!(old_ids.is_empty());
+    }
+
+    #[test]
+    fn test_apply_permutation() {
+        let mut lattice = Lattice::new(6);
+        lattice.insert(0, Direction::RIGHT, 1);
+        lattice.insert(0, Direction::TOP_RIGHT, 2);
+        lattice.insert(1, Direction::RIGHT, 4);
+        let perm = [3, 5, 0, 1, 2, 4];
+        let permuted = lattice.apply_permutation(&perm);
+        permuted.validate();
+        assert!(!permuted.contains(4));
+        assert_eq!(permuted.neighbor(3, Direction::RIGHT), Some(5));
+        assert_eq!(permuted.neighbor(5, Direction::RIGHT), Some(2));
+        let mut expected: Vec<_> = lattice
+            .edges()
+            .map(|(a, b)| {
+                let (a, b) = (perm[a as usize], perm[b as usize]);
+                (a.min(b), a.max(b))
+            })
+            .collect();
+        expected.sort();
+        assert_eq!(permuted.edges().collect::<Vec<_>>(), expected);
+        let mut inverse = [0; 6];
+        for (i, &p) in perm.iter().enumerate() {
+            inverse[p as usize] = i as u32;
+        }
+        let restored = permuted.apply_permutation(&inverse);
+        assert_eq!(
+            restored.edges().collect::<Vec<_>>(),
+            lattice.edges().collect::<Vec<_>>()
+        );
+        assert_eq!(restored.neighbor(1, Direction::RIGHT), Some(4));
+    }
+
+    #[test]
+    #[should_panic(expected = "is not a permutation")]
+    fn test_apply_permutation_rejects_duplicates() {
+        Lattice::new(3).apply_permutation(&[0, 2, 2]);
+    }
+
+    #[test]
+    fn test_degree() {
+        let mut lattice = Lattice::new(10);
+        assert_eq!(lattice.degree(0), 0);
+        for (i, dir) in Direction::all().enumerate() {
+            lattice.insert(0, dir, i as u32 + 1);
+        }
+        lattice.insert(1, Direction::RIGHT, 7);
+        lattice.insert(7, Direction::RIGHT, 8);
+        assert_eq!(lattice.degree(0), 6);
+        assert_eq!(lattice.degree(7), 2);
+        assert_eq!(lattice.degree(9), 0);
+    }
+
+    #[test]
+    fn test_is_empty_is_about_capacity() {
+        assert!(Lattice::new(0).is_empty());
+        let lattice = Lattice::new(3);
+        assert!(!lattice.is_empty());
+        assert_eq!(lattice.iter_nodes().count(), 0);
+    }
+
+    #[test]
+    fn test_edge_count() {
+        assert_eq!(Lattice::new(4).edge_count(), 0);
+        let mut triangle = Lattice::new(3);
+        triangle.insert(0, Direction::RIGHT, 1);
+        triangle.insert(0, Direction::TOP_RIGHT, 2);
+        assert_eq!(triangle.edge_count(), 3);
+        let mut chain = Lattice::new(4);
+        chain.insert(0, Direction::RIGHT, 1);
+        chain.insert(1, Direction::RIGHT, 2);
+        chain.insert(2, Direction::RIGHT, 3);
+        assert_eq!(chain.edge_count(), 3);
+    }
+
+    #[test]
+    fn test_num_edges() {
+        assert_eq!(Lattice::new(4).num_edges(), 0);
+        let mut star = Lattice::new(7);
+        for (dir, id) in Direction::all().step_by(2).zip(1u32..) {
+            star.insert(0, dir, id);
+        }
+        let mut chain = Lattice::new(5);
+        chain.insert_path(
+            0,
+            &[
+                (Direction::RIGHT, 1),
+                (Direction::TOP_RIGHT, 2),
+                (Direction::RIGHT, 3),
+                (Direction::BOTTOM_RIGHT, 4),
+            ],
+        );
+        let mut triangle = Lattice::new(3);
+        triangle.insert(0, Direction::RIGHT, 1);
+        triangle.insert(0, Direction::TOP_RIGHT, 2);
+        for (lattice, expected) in [(&star, 3), (&chain, 4), (&triangle, 3)] {
+            assert_eq!(lattice.num_edges(), expected);
+            assert_eq!(lattice.num_edges(), lattice.edges().count());
+        }
+    }
+
+    #[test]
+    fn test_empty_slots_iter_matches_buffered() {
+        let mut lattice = Lattice::new(12);
+        lattice.insert(0, Direction::RIGHT, 1);
+        lattice.insert(0, Direction::TOP_RIGHT, 2);
+        lattice.insert(0, Direction::TOP_LEFT, 3);
+        lattice.insert(0, Direction::LEFT, 4);
+        lattice.insert(0, Direction::BOTTOM_LEFT, 5);
+        lattice.insert(0, Direction::BOTTOM_RIGHT, 6);
+        lattice.validate();
+        let key =
+            |(id, dir, nbs): (u32, Direction, [Neighbor; 6])| (id, dir.0, nbs.map(|n| n.get()));
+        let mut slots = Vec::new();
+        lattice.empty_slots(&mut Vec::new(), &mut slots);
+        let mut expected: Vec<_> = slots.into_iter().map(key).collect();
+        expected.sort();
+        let mut actual: Vec<_> = lattice.empty_slots_iter().map(key).collect();
+        actual.sort();
+        assert!(!actual.is_empty());
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_empty_slots_skip_unembeddable() {
+        // A chain bent around an empty cell. The cell to the right of 4 is also
+        // next to 2, across the gap, but inserting there would not link them.
+        let mut lattice = Lattice::new(6);
+        lattice.insert(0, Direction::RIGHT, 1);
+        lattice.insert(1, Direction::BOTTOM_RIGHT, 2);
+        lattice.insert(0, Direction::BOTTOM_LEFT, 3);
+        lattice.insert(3, Direction::BOTTOM_RIGHT, 4);
+        lattice.validate();
+        let slots: Vec<_> = lattice.empty_slots_iter().collect();
+        assert!(!slots.is_empty());
+        assert!(
+            !slots
+                .iter()
+                .any(|&(id, dir, _)| (id == 4 && dir == Direction::RIGHT)
+                    || (id == 2 && dir == Direction::BOTTOM_LEFT))
+        );
+        for (id, dir, _) in slots {
+            let mut next = lattice.clone();
+            next.insert(id, dir, 5);
+            next.validate();
+        }
+    }
+
+    #[test]
+    fn test_empty_slots_with_reused_buffers() {
+        let key =
+            |&(id, dir, nbs): &(u32, Direction, [Neighbor; 6])| (id, dir.0, nbs.map(|n| n.get()));
+        // Fill the buffers with a bigger lattice first, so that stale state
+        // from it would show up in the later calls.
+        let mut big = Lattice::new(19);
+        big.insert(0, Direction::RIGHT, 1);
+        let mut bufs = SlotBuffers::default();
+        let mut slots = Vec::new();
+        for id in 2u32..19 {
+            big.empty_slots_with(&mut bufs, &mut slots);
+            let (from, dir, _) = slots[(id as usize * 5) % slots.len()];
+            big.insert(from, dir, id);
+        }
+        // The chain bent around an empty cell, from
+        // `test_empty_slots_skip_unembeddable`.
+        let mut bent = Lattice::new(6);
+        bent.insert(0, Direction::RIGHT, 1);
+        bent.insert(1, Direction::BOTTOM_RIGHT, 2);
+        bent.insert(0, Direction::BOTTOM_LEFT, 3);
+        bent.insert(3, Direction::BOTTOM_RIGHT, 4);
+        let mut visited = Vec::new();
+        let mut expected = Vec::new();
+        for lattice in [&big, &bent, &big] {
+            lattice.empty_slots_with(&mut bufs, &mut slots);
+            lattice.empty_slots(&mut visited, &mut expected);
+            assert!(!slots.is_empty());
+            assert_eq!(
+                slots.iter().map(key).collect::<Vec<_>>(),
+                expected.iter().map(key).collect::<Vec<_>>()
+            );
+        }
+        bent.empty_slots_with(&mut bufs, &mut slots);
+        assert!(
+            !slots
+                .iter()
+                .any(|&(id, dir, _)| (id == 4 && dir == Direction::RIGHT)
+                    || (id == 2 && dir == Direction::BOTTOM_LEFT))
+        );
+    }
+
+    #[test]
+    fn test_empty_slots_single_edge() {
+        let mut lattice = Lattice::new(3);
+        lattice.insert(0, Direction::RIGHT, 1);
+        let slots: Vec<_> = lattice.empty_slots_iter().collect();
+        // Every cell around the edge is a slot: two of them touch both nodes,
+        // and each node has three more that only touch itself.
+        assert_eq!(slots.len(), 8);
+        let shared = slots
+            .iter()
+            .filter(|(_, _, nbs)| Neighbor::count_filled(nbs) == 2)
+            .count();
+        assert_eq!(shared, 2);
+        for (id, dir, _) in slots {
+            assert!(id < 2);
+            let mut next = lattice.clone();
+            next.insert(id, dir, 2);
+            next.validate();
+        }
+    }
+
+    #[test]
+    fn test_slot_filled_directions() {
+        let mut nbs = [Neighbor::default(); 6];
+        assert_eq!(Lattice::slot_filled_directions(&nbs).count(), 0);
+        nbs[Direction::BOTTOM_RIGHT].put(4);
+        nbs[Direction::TOP_RIGHT].put(0);
+        nbs[Direction::LEFT].put(7);
+        assert_eq!(
+            Lattice::slot_filled_directions(&nbs).collect::<Vec<_>>(),
+            vec![
+                Direction::TOP_RIGHT,
+                Direction::LEFT,
+                Direction::BOTTOM_RIGHT
+            ]
+        );
+        // The slot above the edge touches both of its nodes.
+        let mut lattice = Lattice::new(3);
+        lattice.insert(0, Direction::RIGHT, 1);
+        let (_, _, nbs) = lattice
+            .empty_slots_iter()
+            .find(|(_, _, nbs)| {
+                Neighbor::count_filled(nbs) == 2 && nbs[Direction::BOTTOM_LEFT].is_filled()
+            })
+            .unwrap();
+        assert_eq!(
+            Lattice::slot_filled_directions(&nbs).collect::<Vec<_>>(),
+            vec![Direction::BOTTOM_LEFT, Direction::BOTTOM_RIGHT]
+        );
+        assert_eq!(nbs[Direction::BOTTOM_LEFT].get(), Some(0));
+        assert_eq!(nbs[Direction::BOTTOM_RIGHT].get(), Some(1));
+    }
+
+    #[test]
+    fn test_edges_with_duplicate_slot() {
+        let mut lattice = Lattice::new(3);
+        lattice.insert(0, Direction::RIGHT, 1);
+        lattice.insert(0, Direction::TOP_RIGHT, 2);
+        // Corrupt the lattice so that 0 has 1 in two slots.
+        lattice.conn[0][Direction::LEFT].put(1);
+        assert_eq!(
+            lattice.edges().collect::<Vec<_>>(),
+            vec![(0, 1), (0, 2), (1, 2)]
+        );
+    }
+
     #[test]
     fn test_print_non_contiguous_nodes() {
         let mut lattice = Lattice::new(10);
@@ -659,7 +2782,7 @@ mod test {
         lattice.insert(2, Direction::TOP_RIGHT, 5);
         // Separate component with high node IDs
         lattice.insert(7, Direction::RIGHT, 9);
-        check_lattice_consistency(&lattice);
+        lattice.validate();
         let output = format!("{}", lattice);
         // Should contain only the nodes that were actually connected
         assert!(output.contains(" 0 "));
@@ -0,0 +1,9 @@
+pub mod backtracking;
+pub mod baseline;
+pub mod bounds;
+pub mod export;
+pub mod graph;
+pub mod graphml;
+pub mod greedy;
+pub mod lattice;
+pub mod verify;
@@ -1,48 +1,59 @@
-mod graph;
-mod greedy;
-mod lattice;
+use std::process::ExitCode;
 
-use graph::Graph;
-use greedy::solve_greedy;
+use cheers::{
+    export::{print_summary, to_json},
+    graph::Graph,
+    greedy::solve_greedy,
+    verify::verify_cover,
+};
 
-fn main() {
-    let solns = solve_greedy::<Graph>(6);
-    println!("Found {}", solns.len());
-    for soln in solns {
-        println!("=============\n{}", soln);
-    }
-
-    // let mut lattice = Lattice::new(507);
-    // lattice.insert(0, Direction::RIGHT, 1);
-    // let mut visited = Vec::new();
-    // let mut slots = Vec::new();
-    // println!("{}\n===========================\n", lattice);
-    // for id in 2u32..(lattice.len() as u32) {
-    //     lattice.empty_slots(&mut visited, &mut slots);
-    //     let (from, dir, _) = slots.last().unwrap();
-    //     lattice.insert(*from, *dir, id);
-    //     println!("{}\n===========================\n", lattice);
-    // }
-
-    // let mut our_lattice = Lattice::new(9);
-    // our_lattice.insert(0, Direction::TOP_RIGHT, 1);
-    // our_lattice.insert(0, Direction::RIGHT, 2);
-    // our_lattice.insert(1, Direction::RIGHT, 3);
-    // our_lattice.insert(1, Direction::TOP_RIGHT, 4);
-    // our_lattice.insert(1, Direction::TOP_LEFT, 5);
-    // our_lattice.insert(3, Direction::RIGHT, 6);
-    // our_lattice.insert(3, Direction::TOP_RIGHT, 8);
-    // our_lattice.insert(6, Direction::TOP_RIGHT, 7);
-
-    // println!("Our Lattice:\n{}\n", our_lattice);
+/// Number of nodes to solve for, when it is not given on the command line.
+const DEFAULT_NUM_NODES: usize = 19;
 
-    // // Test the graph
-    // let mut graph = Graph::new_complete(12);
-    // println!("{}", graph);
+const USAGE: &str = "Usage: cheers [--json] [NUM_NODES]";
 
-    // graph.remove_edge(0, 1);
-    // graph.remove_edge(5, 7);
-    // graph.remove_edge(9, 11);
-    // println!("After removing some edges:");
-    // println!("{}", graph);
+fn main() -> ExitCode {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    // Print the solution as JSON instead of ASCII art.
+    let json = match args.iter().position(|arg| arg == "--json") {
+        Some(i) => {
+            args.remove(i);
+            true
+        }
+        None => false,
+    };
+    if args.len() > 1 {
+        eprintln!("Unexpected arguments: {}\n{USAGE}", args[1..].join(" "));
+        return ExitCode::FAILURE;
+    }
+    let num_nodes = match args.first() {
+        Some(arg) => match arg.parse::<usize>() {
+            Ok(n) => n,
+            Err(_) => {
+                eprintln!(
+                    "Expected the number of nodes as a non-negative integer, found '{arg}'\n{USAGE}"
+                );
+                return ExitCode::FAILURE;
+            }
+        },
+        None => DEFAULT_NUM_NODES,
+    };
+    let solns = solve_greedy::<Graph>(num_nodes);
+    if json {
+        println!("{}", to_json(&solns));
+    } else {
+        print!("{}", print_summary(&solns));
+        for soln in &solns {
+            println!("=============\n{}", soln);
+        }
+    }
+    if let Err(missing) = verify_cover(num_nodes, &solns) {
+        eprintln!(
+            "The cover is missing {} edges: {:?}",
+            missing.len(),
+            missing
+        );
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
 }
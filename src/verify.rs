@@ -0,0 +1,232 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+    graph::{Graph, TGraph},
+    lattice::Lattice,
+};
+
+/// Check that `solutions` cover every edge of the complete graph with
+/// `num_nodes` nodes. On failure, returns the edges that are not covered by
+/// any lattice, as `(i, j)` pairs with `i < j`, in ascending order.
+pub fn verify_cover(num_nodes: usize, solutions: &[Lattice]) -> Result<(), Vec<(u32, u32)>> {
+    verify_cover_graph(&Graph::new_complete(num_nodes), solutions)
+}
+
+/// Same as `verify_cover`, but checks that `solutions` cover every edge of
+/// `graph` instead of the complete graph.
+pub fn verify_cover_graph(graph: &Graph, solutions: &[Lattice]) -> Result<(), Vec<(u32, u32)>> {
+    let n = graph.num_nodes() as u32;
+    let mut graph = graph.clone();
+    for lattice in solutions {
+        for (a, b) in lattice.edges().filter(|&(a, b)| a < n && b < n) {
+            graph.remove_edge(a, b);
+        }
+    }
+    if graph.is_empty() {
+        return Ok(());
+    }
+    Err(graph.all_edges().collect())
+}
+
+/// Panic if any lattice in `solutions` is broken, or if they don't cover every
+/// edge of the complete graph with `num_nodes` nodes. Shared by the tests of
+/// the solvers.
+#[cfg(test)]
+pub(crate) fn assert_covers(num_nodes: usize, solutions: &[Lattice]) {
+    for lattice in solutions {
+        lattice.validate();
+    }
+    if let Err(missing) = verify_cover(num_nodes, solutions) {
+        panic!("Some edges were not covered: {missing:?}");
+    }
+}
+
+/// Count how many lattices in `solutions` contain each edge, and return the
+/// edges that are covered more than once along with their count. Edges are
+/// returned as `(i, j)` pairs with `i < j`, in ascending order.
+pub fn coverage_multiplicity(num_nodes: usize, solutions: &[Lattice]) -> Vec<((u32, u32), usize)> {
+    let n = num_nodes as u32;
+    let mut counts = HashMap::new();
+    for lattice in solutions {
+        for edge in lattice.edges().filter(|&(a, b)| a < n && b < n) {
+            *counts.entry(edge).or_insert(0usize) += 1;
+        }
+    }
+    let mut out: Vec<_> = counts.into_iter().filter(|&(_, count)| count > 1).collect();
+    out.sort_unstable();
+    out
+}
+
+/// Count the lattices in `solutions` by their number of edges. Maps each size
+/// to the number of lattices of that size.
+pub fn size_histogram(solutions: &[Lattice]) -> BTreeMap<usize, usize> {
+    let mut out = BTreeMap::new();
+    for lattice in solutions {
+        *out.entry(lattice.edge_count()).or_insert(0) += 1;
+    }
+    out
+}
+
+/// Canonical index of the edge `(i, j)` of the complete graph with `num_nodes`
+/// nodes. Edges are numbered in ascending order of `(min(i, j), max(i, j))`,
+/// so the indices run from 0 to `num_nodes * (num_nodes - 1) / 2`. `i` and `j`
+/// must be different, because self loops are not edges, and both must be less
+/// than `num_nodes`.
+pub fn edge_index(num_nodes: usize, i: u32, j: u32) -> usize {
+    debug_assert!(i != j, "Self loop ({i}, {j}) has no edge index");
+    debug_assert!(
+        (i.max(j) as usize) < num_nodes,
+        "({i}, {j}) is not an edge of the complete graph with {num_nodes} nodes"
+    );
+    let (i, j) = (i.min(j) as usize, i.max(j) as usize);
+    i * num_nodes - i * (i + 1) / 2 + (j - i - 1)
+}
+
+/// For each edge of the complete graph with `num_nodes` nodes, in the order of
+/// `edge_index`, find the index of the first lattice in `solutions` that
+/// covers it, or `None` if it is not covered.
+pub fn cover_assignment(num_nodes: usize, solutions: &[Lattice]) -> Vec<Option<usize>> {
+    let n = num_nodes as u32;
+    let mut out = vec![None; num_nodes * num_nodes.saturating_sub(1) / 2];
+    for (li, lattice) in solutions.iter().enumerate() {
+        for (a, b) in lattice.edges().filter(|&(a, b)| a < n && b < n) {
+            out[edge_index(num_nodes, a, b)].get_or_insert(li);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lattice::Direction;
+
+    #[test]
+    fn test_verify_complete_cover() {
+        // K3 is a single triangle.
+        let mut lattice = Lattice::new(3);
+        lattice.insert(0, Direction::RIGHT, 1);
+        lattice.insert(0, Direction::TOP_RIGHT, 2);
+        assert_eq!(verify_cover(3, &[lattice]), Ok(()));
+        assert_eq!(verify_cover(1, &[]), Ok(()));
+    }
+
+    #[test]
+    fn test_verify_incomplete_cover() {
+        let mut lattice = Lattice::new(4);
+        lattice.insert(0, Direction::RIGHT, 1);
+        lattice.insert(0, Direction::TOP_RIGHT, 2);
+        assert_eq!(
+            verify_cover(4, &[lattice]),
+            Err(vec![(0, 3), (1, 3), (2, 3)])
+        );
+        assert_eq!(verify_cover(2, &[]), Err(vec![(0, 1)]));
+    }
+
+    #[test]
+    fn test_verify_cover_graph() {
+        // Without the edge (1, 2), the path 1 - 0 - 2 covers the graph.
+        let graph = Graph::new_complete_minus_matching(3, &[(1, 2)]);
+        let mut lattice = Lattice::new(3);
+        lattice.insert(0, Direction::RIGHT, 1);
+        assert_eq!(
+            verify_cover_graph(&graph, &[lattice.clone()]),
+            Err(vec![(0, 2)])
+        );
+        lattice.insert(0, Direction::LEFT, 2);
+        assert_eq!(verify_cover_graph(&graph, &[lattice]), Ok(()));
+    }
+
+    #[test]
+    fn test_coverage_multiplicity_shared_edge() {
+        let mut first = Lattice::new(4);
+        first.insert(0, Direction::RIGHT, 1);
+        first.insert(0, Direction::TOP_RIGHT, 2);
+        let mut second = Lattice::new(4);
+        second.insert(0, Direction::RIGHT, 1);
+        second.insert(1, Direction::RIGHT, 3);
+        assert_eq!(
+            coverage_multiplicity(4, &[first, second]),
+            vec![((0, 1), 2)]
+        );
+    }
+
+    #[test]
+    fn test_coverage_multiplicity_disjoint() {
+        let mut lattice = Lattice::new(3);
+        lattice.insert(0, Direction::RIGHT, 1);
+        lattice.insert(0, Direction::TOP_RIGHT, 2);
+        assert!(coverage_multiplicity(3, &[lattice]).is_empty());
+    }
+
+    #[test]
+    fn test_size_histogram() {
+        use crate::greedy::solve_greedy;
+        let solution = solve_greedy::<Graph>(7);
+        let histogram = size_histogram(&solution);
+        assert_eq!(histogram.values().sum::<usize>(), solution.len());
+        assert_eq!(
+            histogram
+                .iter()
+                .map(|(size, count)| size * count)
+                .sum::<usize>(),
+            21
+        );
+        let mut triangle = Lattice::new(3);
+        triangle.insert(0, Direction::RIGHT, 1);
+        triangle.insert(0, Direction::TOP_RIGHT, 2);
+        let mut edge = Lattice::new(3);
+        edge.insert(0, Direction::RIGHT, 1);
+        assert_eq!(
+            size_histogram(&[triangle.clone(), edge, triangle]),
+            BTreeMap::from([(1, 1), (3, 2)])
+        );
+        assert!(size_histogram(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_edge_index() {
+        let n = 6;
+        let indices: Vec<_> = (0..n)
+            .flat_map(|i| ((i + 1)..n).map(move |j| (i, j)))
+            .map(|(i, j)| edge_index(n as usize, i, j))
+            .collect();
+        assert_eq!(indices, (0..15).collect::<Vec<_>>());
+        assert_eq!(edge_index(6, 4, 1), edge_index(6, 1, 4));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "Self loop (3, 3) has no edge index")]
+    fn test_edge_index_rejects_self_loop() {
+        edge_index(6, 3, 3);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "(0, 7) is not an edge of the complete graph with 6 nodes")]
+    fn test_edge_index_rejects_out_of_range() {
+        edge_index(6, 0, 7);
+    }
+
+    #[test]
+    fn test_cover_assignment() {
+        use crate::{graph::Graph, greedy::solve_greedy};
+        let solution = solve_greedy::<Graph>(7);
+        let assignment = cover_assignment(7, &solution);
+        assert_eq!(assignment.len(), 21);
+        assert!(
+            assignment
+                .iter()
+                .all(|a| a.is_some_and(|li| li < solution.len()))
+        );
+        for (li, lattice) in solution.iter().enumerate() {
+            for (a, b) in lattice.edges() {
+                assert!(assignment[edge_index(7, a, b)].is_some_and(|first| first <= li));
+            }
+        }
+        let mut lattice = Lattice::new(3);
+        lattice.insert(0, Direction::RIGHT, 1);
+        assert_eq!(cover_assignment(3, &[lattice]), vec![Some(0), None, None]);
+    }
+}
@@ -0,0 +1,71 @@
+use std::process::Command;
+
+use cheers::{graph::Graph, greedy::solve_greedy, verify::verify_cover};
+
+#[test]
+fn test_cli_solves_given_size() {
+    let output = Command::new(env!("CARGO_BIN_EXE_cheers"))
+        .arg("7")
+        .output()
+        .expect("Failed to run the binary");
+    // The binary verifies the cover, and fails if any edge is missing.
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    // The summary table comes first, with a header and one row per lattice.
+    let mut lines = stdout.lines();
+    assert!(
+        lines
+            .next()
+            .is_some_and(|header| header.starts_with("lattice")),
+        "The first line should be the header of the summary"
+    );
+    let found = lines.take_while(|line| !line.starts_with('=')).count();
+    assert_eq!(found, solve_greedy::<Graph>(7).len());
+    assert_eq!(stdout.matches("=============").count(), found);
+}
+
+#[test]
+fn test_cli_rejects_non_numeric() {
+    let output = Command::new(env!("CARGO_BIN_EXE_cheers"))
+        .arg("seven")
+        .output()
+        .expect("Failed to run the binary");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("'seven'"));
+}
+
+#[test]
+fn test_cli_rejects_extra_arguments() {
+    let output = Command::new(env!("CARGO_BIN_EXE_cheers"))
+        .args(["7", "8"])
+        .output()
+        .expect("Failed to run the binary");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Unexpected arguments: 8"));
+    assert!(stderr.contains("Usage:"));
+}
+
+#[test]
+fn test_cli_json_output() {
+    let output = Command::new(env!("CARGO_BIN_EXE_cheers"))
+        .args(["--json", "7"])
+        .output()
+        .expect("Failed to run the binary");
+    assert!(output.status.success());
+    let parsed: Vec<Vec<(u32, u32)>> = serde_json::from_slice(&output.stdout).unwrap();
+    let solution = solve_greedy::<Graph>(7);
+    assert_eq!(verify_cover(7, &solution), Ok(()));
+    let expected: Vec<Vec<(u32, u32)>> = solution
+        .iter()
+        .map(|lattice| lattice.edges().collect())
+        .collect();
+    assert_eq!(parsed, expected);
+    // Every edge of K7 must appear in the parsed output.
+    for i in 0..7 {
+        for j in (i + 1)..7 {
+            assert!(parsed.iter().flatten().any(|&edge| edge == (i, j)));
+        }
+    }
+}
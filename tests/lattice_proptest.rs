@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+
+use cheers::lattice::{Direction, Lattice};
+use proptest::prelude::*;
+
+const NUM_NODES: usize = 16;
+
+#[derive(Debug, Clone)]
+enum Op {
+    /// Insert a node into one of the empty slots reported by the lattice. The
+    /// numbers pick the slot and the new node.
+    Insert(usize, usize),
+    /// Remove one of the nodes in the lattice.
+    Remove(usize),
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        3 => (any::<usize>(), any::<usize>()).prop_map(|(slot, node)| Op::Insert(slot, node)),
+        1 => any::<usize>().prop_map(Op::Remove),
+    ]
+}
+
+/// Panics if the nodes of a component can't be laid out on a triangular grid,
+/// i.e. two nodes land at the same position, or two nodes at adjacent positions
+/// are not linked.
+fn check_layout(lattice: &Lattice) {
+    for component in lattice.components() {
+        let start = component[0];
+        let mut coords = HashMap::from([(start, (0isize, 0isize))]);
+        let mut stack = vec![start];
+        while let Some(id) = stack.pop() {
+            for (nb, x, y) in lattice.neighbors_with_coords(id, coords[&id]) {
+                if let Some(&pos) = coords.get(&nb) {
+                    assert_eq!(pos, (x, y), "Node {nb} has two positions");
+                } else {
+                    coords.insert(nb, (x, y));
+                    stack.push(nb);
+                }
+            }
+        }
+        let nodes: HashMap<_, _> = coords.iter().map(|(&id, &pos)| (pos, id)).collect();
+        assert_eq!(nodes.len(), coords.len(), "Two nodes share a position");
+        for (&(x, y), &id) in &nodes {
+            for (dir, (dx, dy)) in [(1, 0), (0, 1), (-1, 1), (-1, 0), (0, -1), (1, -1)]
+                .into_iter()
+                .enumerate()
+            {
+                if let Some(&nb) = nodes.get(&(x + dx, y + dy)) {
+                    assert!(
+                        lattice.contains_edge(id, nb),
+                        "Nodes {id} and {nb} are adjacent in direction {dir} but not linked"
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn apply(lattice: &mut Lattice, op: &Op) {
+    let absent: Vec<u32> = (0..(NUM_NODES as u32))
+        .filter(|&id| !lattice.contains(id))
+        .collect();
+    match *op {
+        Op::Insert(slot, node) => {
+            let slots: Vec<_> = lattice.empty_slots_iter().collect();
+            if slots.is_empty() {
+                // Nothing to grow from, so start with an edge.
+                if absent.len() >= 2 {
+                    let a = absent[node % absent.len()];
+                    let b = absent[(node + 1) % absent.len()];
+                    lattice.insert(a, Direction::RIGHT, b);
+                }
+            } else if !absent.is_empty() {
+                let (id, dir, _) = slots[slot % slots.len()];
+                lattice.insert(id, dir, absent[node % absent.len()]);
+            }
+        }
+        Op::Remove(node) => {
+            let present: Vec<u32> = lattice.iter_nodes().collect();
+            if !present.is_empty() {
+                assert!(lattice.remove(present[node % present.len()]));
+            }
+        }
+    }
+}
+
+#[test]
+fn regression_hole_in_lattice() {
+    // Found by the property test below: removing the middle of a full ring
+    // leaves a hole, and walking the boundary of that hole used to loop
+    // forever.
+    let mut lattice = Lattice::new(NUM_NODES);
+    for (dir, id) in Direction::all().zip(1u32..) {
+        lattice.insert(0, dir, id);
+    }
+    assert!(lattice.remove(0));
+    for op in [
+        Op::Insert(3, 5),
+        Op::Remove(2),
+        Op::Insert(7, 1),
+        Op::Insert(0, 0),
+    ] {
+        apply(&mut lattice, &op);
+        lattice.validate();
+        check_layout(&lattice);
+    }
+}
+
+proptest! {
+    #[test]
+    fn insert_and_remove_keep_lattice_valid(ops in prop::collection::vec(op(), 1..60)) {
+        let mut lattice = Lattice::new(NUM_NODES);
+        for op in &ops {
+            apply(&mut lattice, op);
+            lattice.validate();
+            check_layout(&lattice);
+        }
+    }
+}